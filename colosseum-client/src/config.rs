@@ -1,18 +1,19 @@
 // Copyright 2021 Chay Nabors.
 
+use std::cell::OnceCell;
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
 use std::net::SocketAddrV4;
 
-use colosseum_core::bodywear::BodywearIdentifier;
-use colosseum_core::combatant::Combatant;
-use colosseum_core::footwear::FootwearIdentifier;
-use colosseum_core::gender::Gender;
-use colosseum_core::handwear::HandwearIdentifier;
-use colosseum_core::legwear::LegwearIdentifier;
+use colosseum_core::archetype::Archetype;
+use colosseum_core::archetype::ArchetypeIdentifier;
+use colosseum_core::controller::ControllerKind;
+use colosseum_core::encounter::EncounterIdentifier;
+use colosseum_core::locale::Localizer;
+use colosseum_core::locale::DEFAULT_LOCALE;
 use colosseum_core::party::Party;
-use colosseum_core::skill::SkillIdentifier;
-use colosseum_core::weapon::WeaponIdentifier;
+use colosseum_core::roster::Roster;
+use colosseum_core::roster::RosterIdentifier;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -20,7 +21,22 @@ use serde::Serialize;
 pub struct Config {
     pub server_address: SocketAddr,
     pub resolution: [u32; 2],
-    pub test_party: Party,
+    /// The server roster played with
+    pub roster: RosterIdentifier,
+    /// Plays a party of these archetypes instead of the roster
+    #[serde(default)]
+    pub party: Option<Vec<ArchetypeIdentifier>>,
+    /// Plays the roster against one of the server's encounters instead of another player
+    #[serde(default)]
+    pub encounter: Option<EncounterIdentifier>,
+    /// Lets a bot take every turn in place of the player
+    #[serde(default)]
+    pub auto_battle: Option<ControllerKind>,
+    /// The locale text is shown in, as in `es` or `pt-BR`, falling back to the default locale where untranslated
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(skip)]
+    localizer: OnceCell<Localizer>,
 }
 
 impl Default for Config {
@@ -28,43 +44,32 @@ impl Default for Config {
         Self {
             server_address: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 20000)),
             resolution: [1280, 720],
-            test_party: Party {
-                members: vec![Combatant {
-                    name: "Angelo".into(),
-                    gender: Gender::Male,
-                    skills: vec![SkillIdentifier::Sweep],
-
-                    agility: 10.,
-                    dexterity: 13.,
-                    intelligence: 6.,
-                    mind: 8.,
-                    strength: 5.,
-                    vigor: 20.,
-                    vitality: 12.,
-
-                    bodywear: Some(BodywearIdentifier::BreakersLongsleeve),
-                    footwear: Some(FootwearIdentifier::BreakersSneakers),
-                    handwear: Some(HandwearIdentifier::BreakersWraps),
-                    headwear: None,
-                    legwear: Some(LegwearIdentifier::BreakersHaremPants),
-                    weapon: Some(WeaponIdentifier::PipeIron),
-
-                    hp: 20.,
-                    fatigue: f64::MAX,
-                    dots: vec![],
+            roster: "breakers".parse().unwrap(),
+            party: None,
+            encounter: None,
+            auto_battle: None,
+            locale: None,
+            localizer: OnceCell::new(),
+        }
+    }
+}
 
-                    agility_modifiers: vec![],
-                    dexterity_modifiers: vec![],
-                    intelligence_modifiers: vec![],
-                    mind_modifiers: vec![],
-                    strength_modifiers: vec![],
-                    vigor_modifiers: vec![],
-                    vitality_modifiers: vec![],
-                }],
+impl Config {
+    /// The party played with, built from the configured archetypes or else the roster
+    pub fn party(&self) -> Party {
+        match &self.party {
+            Some(members) => Party {
+                members: members.iter().map(|member| <&Archetype>::from(*member).combatant()).collect(),
                 inventory: vec![],
             },
+            None => <&Roster>::from(self.roster).party(),
         }
     }
+
+    /// Looks up the text shown to the player, built on first use and shared by every state holding the config
+    pub fn localizer(&self) -> &Localizer {
+        self.localizer.get_or_init(|| Localizer::new(self.locale.as_deref().unwrap_or(DEFAULT_LOCALE)))
+    }
 }
 
 mod test {
@@ -93,7 +93,7 @@ impl CombatState {
         let mut current_state = self.turn_states.pop().expect("Combat state has no turn states to mutate");
 
         let state_transition = match &mut current_state {
-            TurnState::WaitingState(state) => state.handle_event(event),
+            TurnState::WaitingState(state) => state.handle_event(event, server_address, socket),
             TurnState::ActionState(state) => state.handle_event(event, server_address, socket),
             TurnState::SkillState(state) => state.handle_event(event),
             TurnState::TargetingState(state) => state.handle_event(event),
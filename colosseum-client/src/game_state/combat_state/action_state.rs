@@ -7,9 +7,10 @@ use std::rc::Rc;
 use colosseum_core::combat_event::CombatEvent;
 use colosseum_core::combat_state::CombatState;
 use colosseum_core::effect::TargetingScheme;
+use colosseum_core::message::Payload;
 use colosseum_core::skill::SkillIdentifier;
 use colosseum_core::target::Target;
-use colosseum_core::weapon::Weapon;
+use log::info;
 use log::warn;
 
 use super::confirmation_state::ConfirmationState;
@@ -48,6 +49,14 @@ impl Action {
             Action::Skip => Action::Skill,
         }
     }
+
+    fn text_id(&self) -> &'static str {
+        match self {
+            Action::Attack => "action.attack",
+            Action::Skill => "action.skill",
+            Action::Skip => "action.skip",
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -89,23 +98,28 @@ impl ActionState {
 
                     if let Some(keycode) = event.virtual_keycode {
                         match keycode {
-                            KeyCode::S => self.action = self.action.next(),
-                            KeyCode::W => self.action = self.action.prev(),
+                            KeyCode::S | KeyCode::W => {
+                                self.action = match keycode {
+                                    KeyCode::S => self.action.next(),
+                                    _ => self.action.prev(),
+                                };
+                                info!("{}", self.config.localizer().interface(self.action.text_id()));
+                            },
                             KeyCode::Return => match self.action {
                                 Action::Attack => {
                                     let active = self.active;
                                     let active =
                                         &self.shared_state.borrow().parties[active.party_index].members[active.member_index];
-                                    let targeting_scheme = match active.weapon {
-                                        Some(weapon) => <&Weapon>::from(weapon).effect.targeting_scheme,
-                                        None => TargetingScheme::SingleTarget,
-                                    };
+                                    let targeting_scheme = active.attack_targeting_scheme();
 
                                     let viable_targets = self.shared_state.borrow().get_target_list();
 
                                     if viable_targets.len() == 0 {
-                                        match active.weapon {
-                                            Some(weapon) => warn!("No valid targets for weapon: {}", weapon),
+                                        match active.weapon_identifier() {
+                                            Some(weapon) => {
+                                                let name = self.config.localizer().display_name(weapon);
+                                                warn!("No valid targets for weapon: {}", name)
+                                            },
                                             None => warn!("No valid targets for an unarmed attack"),
                                         }
                                     } else {
@@ -137,7 +151,7 @@ impl ActionState {
                                     return StateTransition::New(TurnState::SkillState(SkillState::from_action_state(self)))
                                 },
                                 Action::Skip => {
-                                    socket.send_message(server_address, &CombatEvent::SkipEvent);
+                                    socket.send_message(server_address, Payload::CombatEvent(CombatEvent::SkipEvent));
                                     return StateTransition::New(TurnState::WaitingState(WaitingState::new(
                                         self.config.clone(),
                                         self.shared_state.clone(),
@@ -6,6 +6,7 @@ use std::rc::Rc;
 
 use colosseum::combat_event::CombatEvent;
 use colosseum::combat_state::CombatState;
+use colosseum::message::Payload;
 use colosseum::target::Target;
 use gear::event::Event;
 use gear::event::InputEvent;
@@ -74,7 +75,7 @@ impl ConfirmationState {
                     let keycode = event.virtual_keycode.unwrap();
                     match keycode {
                         KeyCode::Return => {
-                            socket.send_message(server_address, &self.event);
+                            socket.send_message(server_address, Payload::CombatEvent(self.event.clone()));
                             return StateTransition::New(TurnState::WaitingState(WaitingState::new(
                                 self.config.clone(),
                                 self.shared_state.clone(),
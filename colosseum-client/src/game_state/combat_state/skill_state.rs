@@ -65,9 +65,10 @@ impl SkillState {
                             let viable_targets = self.shared_state.borrow().get_target_list();
 
                             if viable_targets.len() == 0 {
-                                warn!("No valid targets for skill: {}", skill_identifier);
+                                let name = self.config.localizer().display_name(skill_identifier);
+                                warn!("No valid targets for skill: {}", name);
                             } else {
-                                let targeting_scheme = skill.effect.targeting_scheme;
+                                let targeting_scheme = skill.effect.targeting_scheme();
 
                                 match targeting_scheme {
                                     TargetingScheme::All => {
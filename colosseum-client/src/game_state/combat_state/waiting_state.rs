@@ -1,21 +1,23 @@
 // Copyright 2021 Chay Nabors.
 
 use std::cell::RefCell;
-use std::convert::TryFrom;
+use std::net::SocketAddr;
 use std::rc::Rc;
 
-use colosseum::combat_event::CombatEvent;
 use colosseum::combat_state::CombatState;
-use colosseum::message::Message;
-use colosseum::message::MessageType;
-use colosseum::message::TakeTurn;
+use colosseum::controller::Controller;
+use colosseum::message::Envelope;
+use colosseum::message::Payload;
 use gear::event::Event;
+use gear::Socket;
 use log::info;
+use log::warn;
 
 use super::action_state::ActionState;
 use super::StateTransition;
 use super::TurnState;
 use crate::config::Config;
+use crate::socket::ClientSocket;
 
 #[derive(Debug)]
 pub struct WaitingState {
@@ -28,7 +30,12 @@ impl WaitingState {
         Self { config, shared_state }
     }
 
-    pub fn handle_event(&mut self, event: &Event) -> StateTransition<TurnState> {
+    pub fn handle_event(
+        &mut self,
+        event: &Event,
+        server_address: SocketAddr,
+        socket: &Socket,
+    ) -> StateTransition<TurnState> {
         match event {
             Event::NetworkEvent(event) => match event {
                 gear::event::NetworkEvent::Message(packet) => {
@@ -36,20 +43,50 @@ impl WaitingState {
                         return StateTransition::None;
                     }
 
-                    let message = bincode::deserialize::<Message>(packet.payload()).unwrap();
-                    info!("Received message from server: {}", message.type_);
+                    let envelope = match Envelope::decode(packet.payload()) {
+                        Ok(envelope) => envelope,
+                        Err(e) => {
+                            warn!("Ignored a message from the server: {}", e);
+                            return StateTransition::None;
+                        },
+                    };
+                    info!("Received message {} from server: {}", envelope.sequence, envelope.payload);
+
+                    match envelope.payload {
+                        Payload::TakeTurn(take_turn) => {
+                            if take_turn.checksum != self.shared_state.borrow().checksum() {
+                                warn!("Combat state diverged from the server before our turn, requesting a resync");
+                                socket.send_message(server_address, Payload::Resync);
+                                return StateTransition::None;
+                            }
+
+                            if let Some(auto_battle) = self.config.auto_battle {
+                                let mut controller = auto_battle.build(rand::random());
+                                let event = controller.choose(&self.shared_state.borrow(), take_turn.target);
+                                socket.send_message(server_address, Payload::CombatEvent(event));
+                                return StateTransition::None;
+                            }
 
-                    match message.type_ {
-                        MessageType::TakeTurn => {
-                            let take_turn = TakeTurn::try_from(&message).unwrap();
                             return StateTransition::New(TurnState::ActionState(ActionState::from_waiting_state(
                                 self,
                                 take_turn.target,
                             )));
                         },
-                        MessageType::CombatEvent => {
-                            let event = CombatEvent::try_from(&message).unwrap();
-                            self.shared_state.borrow_mut().process_event(&event);
+                        Payload::RelayedEvent(relayed) => {
+                            let mut shared_state = self.shared_state.borrow_mut();
+                            shared_state.process_event(&relayed.event);
+
+                            if relayed.checksum != shared_state.checksum() {
+                                warn!("Combat state diverged from the server, requesting a resync");
+                                socket.send_message(server_address, Payload::Resync);
+                                return StateTransition::None;
+                            }
+
+                            // mirror the server readying the next combatant
+                            if !shared_state.is_over() {
+                                shared_state.next_combatant();
+                            }
+                            drop(shared_state);
 
                             info!("Combat event result:");
 
@@ -59,6 +96,17 @@ impl WaitingState {
                                 }
                             }
                         },
+                        Payload::Loot(loot) => {
+                            for item in &loot.items {
+                                info!("Won {:?} {}", item.rarity, self.config.localizer().item_name(item.base));
+                            }
+                            // mirror the server adding the loot to the players' inventory
+                            self.shared_state.borrow_mut().parties[0].inventory.extend(loot.items);
+                        },
+                        Payload::CombatState(combat_state) => {
+                            info!("Resynced combat state with the server");
+                            *self.shared_state.borrow_mut() = combat_state;
+                        },
                         _ => (),
                     }
                 },
@@ -1,26 +1,31 @@
 // Copyright 2021 Chay Nabors.
 
-use std::convert::TryFrom;
 use std::rc::Rc;
 use std::time::Duration;
 
-use colosseum::message::Message;
-use colosseum::message::ProtocolVersion;
+use colosseum::content;
+use colosseum::content::Registry;
+use colosseum::message::Envelope;
+use colosseum::message::Handshake;
+use colosseum::message::Payload;
+use colosseum::message::PROTOCOL_VERSION;
 use gear::event::Event;
 use gear::event::InputEvent;
 use gear::event::NetworkEvent;
 use gear::Engine;
 use gear::KeyCode;
 use gear::NetworkConfig;
-use gear::Packet;
 use gear::Socket;
+use log::error;
 use log::info;
+use log::warn;
 
 use super::splash_state::SplashState;
 use crate::config::Config;
 use crate::game_state::menu_state::navigation_state::NavigationState;
 use crate::game_state::menu_state::MenuSubState;
 use crate::game_state::state_transition::StateTransition;
+use crate::socket::ClientSocket;
 
 pub struct ConnectingState {
     pub config: Rc<Config>,
@@ -43,8 +48,7 @@ impl ConnectingState {
             )
             .unwrap();
 
-        let protover = Message::try_from(&ProtocolVersion(0)).unwrap();
-        socket.send(Packet::reliable_ordered(config.server_address, bincode::serialize(&protover).unwrap(), None));
+        socket.send_message(config.server_address, Payload::Handshake(Handshake::current()));
 
         Self { config, socket: Rc::new(socket) }
     }
@@ -67,15 +71,48 @@ impl ConnectingState {
                         return StateTransition::None;
                     }
 
-                    let message = bincode::deserialize::<Message>(packet.payload()).unwrap();
-                    info!("Received message from server: {}", message.type_);
+                    let envelope = match Envelope::decode(packet.payload()) {
+                        Ok(envelope) => envelope,
+                        Err(e) => {
+                            warn!("Ignored a message from the server: {}", e);
+                            return StateTransition::None;
+                        },
+                    };
+                    info!("Received message {} from server: {}", envelope.sequence, envelope.payload);
+
+                    match envelope.payload {
+                        Payload::ContentPush(push) => {
+                            match Registry::from_pushed(push.files) {
+                                Ok(registry) => {
+                                    let registry = content::install(registry);
+                                    info!("Playing with the server's content version {}", registry.version());
+                                },
+                                Err(e) => error!("Failed to load the server's content: {}", e),
+                            }
+                            StateTransition::None
+                        },
+                        Payload::Handshake(handshake) => {
+                            let version = envelope.version;
+                            info!("Server running protocol version: {}", version);
+                            if version != PROTOCOL_VERSION {
+                                error!("Server speaks protocol version {}, expected {}", version, PROTOCOL_VERSION);
+                                return StateTransition::Old;
+                            }
+
+                            let differences = content::registry().manifest().differences(&handshake.content);
+                            if !differences.is_empty() {
+                                warn!("Content differs from the server's version {}", handshake.content.version);
+                                for difference in &differences {
+                                    warn!("  {}", difference);
+                                }
+                            }
 
-                    match message.type_ {
-                        colosseum::message::MessageType::ProtocolVersion => {
-                            let protover = ProtocolVersion::try_from(&message).unwrap();
-                            info!("Server running protocol version: {:?}", protover);
                             StateTransition::New(MenuSubState::NavigationState(NavigationState::from_connecting_state(self)))
                         },
+                        Payload::Rejected(rejection) => {
+                            error!("Server turned the connection away: {}", rejection.reason);
+                            StateTransition::Old
+                        },
                         _ => StateTransition::None,
                     }
                 },
@@ -1,10 +1,11 @@
 // Copyright 2021 Chay Nabors.
 
-use std::convert::TryFrom;
 use std::rc::Rc;
 
-use colosseum::combat_state::CombatState as InternalCombatState;
-use colosseum::message::Message;
+use colosseum::message::ChallengeEncounter;
+use colosseum::message::ChooseRoster;
+use colosseum::message::Envelope;
+use colosseum::message::Payload;
 use gear::event::Event;
 use gear::event::InputEvent;
 use gear::event::NetworkEvent;
@@ -13,6 +14,7 @@ use gear::KeyCode;
 use gear::KeyState;
 use gear::Socket;
 use log::info;
+use log::warn;
 
 use super::connecting_state::ConnectingState;
 use super::MenuSubState;
@@ -42,6 +44,13 @@ impl MenuOption {
             MenuOption::Quit => MenuOption::Play,
         }
     }
+
+    fn text_id(self) -> &'static str {
+        match self {
+            MenuOption::Play => "menu.play",
+            MenuOption::Quit => "menu.quit",
+        }
+    }
 }
 
 pub struct NavigationState {
@@ -76,13 +85,35 @@ impl NavigationState {
 
                         if let Some(keycode) = event.virtual_keycode {
                             match keycode {
-                                KeyCode::S => self.option = self.option.next(),
-                                KeyCode::W => self.option = self.option.prev(),
+                                KeyCode::S | KeyCode::W => {
+                                    self.option = match keycode {
+                                        KeyCode::S => self.option.next(),
+                                        _ => self.option.prev(),
+                                    };
+                                    info!("{}", self.config.localizer().interface(self.option.text_id()));
+                                },
                                 KeyCode::Return => match self.option {
                                     MenuOption::Play => {
                                         let server_address = self.config.server_address;
-                                        let party = &self.config.test_party;
-                                        self.socket.send_message(server_address, party);
+                                        match self.config.encounter {
+                                            Some(encounter) => {
+                                                let party = self.config.party();
+                                                let challenge = ChallengeEncounter { encounter, party };
+                                                let payload = Payload::ChallengeEncounter(challenge);
+                                                self.socket.send_message(server_address, payload);
+                                            },
+                                            None => match self.config.party {
+                                                Some(_) => {
+                                                    let party = Payload::Party(self.config.party());
+                                                    self.socket.send_message(server_address, party);
+                                                },
+                                                None => {
+                                                    let choice = ChooseRoster { roster: self.config.roster };
+                                                    let payload = Payload::ChooseRoster(choice);
+                                                    self.socket.send_message(server_address, payload);
+                                                },
+                                            },
+                                        }
                                         self.waiting_for_state = true;
                                     },
                                     MenuOption::Quit => engine.terminate(),
@@ -101,14 +132,35 @@ impl NavigationState {
                         return StateTransition::None;
                     }
 
-                    let message = bincode::deserialize::<Message>(packet.payload()).unwrap();
-                    info!("Received message from server: {}", message.type_);
+                    let envelope = match Envelope::decode(packet.payload()) {
+                        Ok(envelope) => envelope,
+                        Err(e) => {
+                            warn!("Ignored a message from the server: {}", e);
+                            return StateTransition::None;
+                        },
+                    };
+                    info!("Received message {} from server: {}", envelope.sequence, envelope.payload);
 
-                    match message.type_ {
-                        colosseum::message::MessageType::CombatState => {
+                    match envelope.payload {
+                        Payload::Rosters(offered) => {
+                            if self.config.party.is_none() && !offered.rosters.contains(&self.config.roster) {
+                                warn!("The server does not offer roster '{}'", self.config.roster);
+                            }
+                        },
+                        Payload::Encounters(hosted) => {
+                            if let Some(encounter) = self.config.encounter.filter(|e| !hosted.encounters.contains(e)) {
+                                warn!("The server does not host encounter '{}'", encounter);
+                            }
+                        },
+                        Payload::CombatState(mut shared_state) => {
                             if self.waiting_for_state {
-                                let shared_state = InternalCombatState::try_from(&message).unwrap();
                                 info!("Received shared state from server");
+
+                                // the server readies the first combatant as soon as it sends the state
+                                if !shared_state.is_over() {
+                                    shared_state.next_combatant();
+                                }
+
                                 return StateTransition::Change(GameState::CombatState(CombatState::new(
                                     self.config.clone(),
                                     self.socket.clone(),
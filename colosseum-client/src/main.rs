@@ -10,6 +10,7 @@ use std::io::Write;
 use camera::Camera;
 use config::Config;
 use error::Error;
+use log::error;
 use nalgebra::Point3;
 use nalgebra::UnitQuaternion;
 use renderer::Renderer;
@@ -29,12 +30,15 @@ async fn main() {
         .init();
 
     let config = serde_json::from_slice::<Config>(include_bytes!("../config.json")).unwrap();
+    if let Err(e) = config.party().validate() {
+        error!("The configured party can't enter combat: {}", e);
+    }
     let resolution = config.resolution;
     let mut resolution = PhysicalSize::new(resolution[0], resolution[1]);
 
     let event_loop = EventLoop::new();
     let window = match WindowBuilder::new()
-        .with_title("Colosseum")
+        .with_title(config.localizer().interface("window.title"))
         .with_inner_size(resolution)
         .build(&event_loop)
     {
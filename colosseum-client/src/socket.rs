@@ -1,26 +1,22 @@
 // Copyright 2021 Chay Nabors.
 
-use std::convert::TryInto;
-use std::fmt::Debug;
 use std::net::SocketAddr;
 
-use colosseum_core::message::Message;
+use colosseum_core::message::Envelope;
+use colosseum_core::message::Payload;
+use log::error;
 
 pub trait ClientSocket {
-    fn send_message<T>(&self, address: SocketAddr, message: T)
-    where
-        T: TryInto<Message>,
-        T::Error: Debug;
+    /// Sends the payload in an envelope, logging rather than returning failures to encode it
+    fn send_message(&self, address: SocketAddr, payload: Payload);
 }
 
 impl ClientSocket for Socket {
-    fn send_message<T>(&self, address: SocketAddr, message: T)
-    where
-        T: TryInto<Message>,
-        T::Error: Debug,
-    {
-        let message: Message = message.try_into().unwrap();
-        let payload = bincode::serialize(&message).unwrap();
-        self.send(Packet::reliable_ordered(address, payload, None));
+    fn send_message(&self, address: SocketAddr, payload: Payload) {
+        let name = payload.to_string();
+        match Envelope::new(payload).encode() {
+            Ok(bytes) => self.send(Packet::reliable_ordered(address, bytes, None)),
+            Err(e) => error!("Failed to send {} to {}: {}", name, address, e),
+        }
     }
 }
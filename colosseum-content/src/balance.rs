@@ -0,0 +1,319 @@
+// Copyright 2021 Chay Nabors.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::iter;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::ValueEnum;
+use colosseum_core::attribute::Attribute;
+use colosseum_core::bodywear::Bodywear;
+use colosseum_core::bodywear::BodywearIdentifier;
+use colosseum_core::combat_state::UNARMED_SUB_EFFECT;
+use colosseum_core::combatant::Combatant;
+use colosseum_core::consumable::Consumable;
+use colosseum_core::consumable::ConsumableIdentifier;
+use colosseum_core::effect::SubEffect;
+use colosseum_core::footwear::Footwear;
+use colosseum_core::footwear::FootwearIdentifier;
+use colosseum_core::gender::Gender;
+use colosseum_core::handwear::Handwear;
+use colosseum_core::handwear::HandwearIdentifier;
+use colosseum_core::headwear::Headwear;
+use colosseum_core::headwear::HeadwearIdentifier;
+use colosseum_core::item::ItemInstance;
+use colosseum_core::legwear::Legwear;
+use colosseum_core::legwear::LegwearIdentifier;
+use colosseum_core::lifetime::Lifetime;
+use colosseum_core::skill::Skill;
+use colosseum_core::skill::SkillIdentifier;
+use colosseum_core::weapon::Weapon;
+use colosseum_core::weapon::WeaponIdentifier;
+use strum::IntoEnumIterator;
+
+#[derive(clap::Args)]
+pub struct BalanceArgs {
+    /// The layout of the table
+    #[arg(short, long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+
+    /// The value of every attribute of both the attacker and the defender
+    #[arg(short, long, default_value_t = 10.)]
+    reference: f64,
+
+    /// Overrides a single reference attribute, as in `strength=14`
+    #[arg(short, long = "attribute", value_parser = parse_attribute)]
+    attributes: Vec<(Attribute, f64)>,
+
+    /// Turns that damage over time without an end is counted for
+    #[arg(long, default_value_t = 10)]
+    horizon: u32,
+
+    /// Writes the table to a file instead of standard output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Markdown,
+}
+
+/// A source of damage and everything needed to work out what it deals
+struct Source {
+    name: String,
+    sub_effects: Vec<SubEffect>,
+    weapon: Option<WeaponIdentifier>,
+}
+
+pub fn run(args: BalanceArgs) -> anyhow::Result<()> {
+    let spread = |attribute: Attribute| {
+        args.attributes.iter().rev().find(|(overridden, _)| *overridden == attribute).map_or(args.reference, |o| o.1)
+    };
+    let attacker = reference_combatant("Attacker", spread);
+    let sources = sources();
+
+    let mut rows = vec![];
+    for defender in defenders(reference_combatant("Defender", spread)) {
+        let damage = sources.iter().map(|source| expected_damage(&attacker, &defender, source, args.horizon)).collect();
+        rows.push((wearables_name(&defender), damage));
+    }
+
+    let columns: Vec<&str> = sources.iter().map(|source| source.name.as_str()).collect();
+    let table = match args.format {
+        Format::Csv => csv(&columns, &rows),
+        Format::Markdown => markdown(&columns, &rows),
+    };
+
+    match &args.output {
+        Some(path) => fs::write(path, table).with_context(|| format!("writing {:?}", path))?,
+        None => print!("{}", table),
+    }
+
+    Ok(())
+}
+
+fn parse_attribute(s: &str) -> Result<(Attribute, f64), String> {
+    let (name, value) = s.split_once('=').ok_or_else(|| format!("expected 'attribute=value', got '{}'", s))?;
+    let attribute = Attribute::iter()
+        .find(|attribute| format!("{:?}", attribute).eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown attribute '{}'", name))?;
+    let value = value.parse().map_err(|_| format!("'{}' is not a number", value))?;
+
+    Ok((attribute, value))
+}
+
+fn reference_combatant<F: Fn(Attribute) -> f64>(name: &str, spread: F) -> Combatant {
+    let mut combatant = Combatant {
+        name: name.into(),
+        gender: Gender::None,
+        skills: vec![],
+        agility: spread(Attribute::Agility),
+        dexterity: spread(Attribute::Dexterity),
+        intelligence: spread(Attribute::Intelligence),
+        mind: spread(Attribute::Mind),
+        strength: spread(Attribute::Strength),
+        vigor: spread(Attribute::Vigor),
+        vitality: spread(Attribute::Vitality),
+        bodywear: None,
+        footwear: None,
+        handwear: None,
+        headwear: None,
+        legwear: None,
+        weapon: None,
+        offhand: None,
+        accessory: None,
+        hp: 0.,
+        fatigue: 0.,
+        dots: vec![],
+        agility_modifiers: vec![],
+        dexterity_modifiers: vec![],
+        intelligence_modifiers: vec![],
+        mind_modifiers: vec![],
+        strength_modifiers: vec![],
+        vigor_modifiers: vec![],
+        vitality_modifiers: vec![],
+    };
+    combatant.hp = combatant.hp_max();
+    combatant
+}
+
+/// Unarmed attacks, attacks with every weapon, then every skill and consumable
+fn sources() -> Vec<Source> {
+    let mut sources =
+        vec![Source { name: "Attack (unarmed)".into(), sub_effects: vec![UNARMED_SUB_EFFECT], weapon: None }];
+
+    for weapon in WeaponIdentifier::iter() {
+        let stored = <&Weapon>::from(weapon);
+        sources.push(Source {
+            name: format!("Attack ({})", stored.display_name),
+            sub_effects: stored.effect.sub_effects.clone(),
+            weapon: Some(weapon),
+        });
+    }
+    for skill in SkillIdentifier::iter() {
+        let stored = <&Skill>::from(skill);
+        sources.push(Source {
+            name: stored.display_name.clone(),
+            sub_effects: stored.effect.sub_effects.clone(),
+            weapon: None,
+        });
+    }
+    for consumable in ConsumableIdentifier::iter() {
+        let stored = <&Consumable>::from(consumable);
+        sources.push(Source {
+            name: stored.display_name.clone(),
+            sub_effects: stored.effect.sub_effects.clone(),
+            weapon: None,
+        });
+    }
+
+    sources
+}
+
+/// The reference defender in every combination of wearables, each slot either empty or holding one of its items
+fn defenders(reference: Combatant) -> Vec<Combatant> {
+    let mut defenders = vec![reference];
+    defenders = equip(defenders, BodywearIdentifier::iter(), |defender, item| defender.bodywear = item);
+    defenders = equip(defenders, FootwearIdentifier::iter(), |defender, item| defender.footwear = item);
+    defenders = equip(defenders, HandwearIdentifier::iter(), |defender, item| defender.handwear = item);
+    defenders = equip(defenders, HeadwearIdentifier::iter(), |defender, item| defender.headwear = item);
+    defenders = equip(defenders, LegwearIdentifier::iter(), |defender, item| defender.legwear = item);
+    defenders
+}
+
+fn equip<T: Copy, I: Iterator<Item = T>, F: Fn(&mut Combatant, Option<ItemInstance<T>>)>(
+    defenders: Vec<Combatant>,
+    items: I,
+    slot: F,
+) -> Vec<Combatant> {
+    let items: Vec<Option<T>> = iter::once(None).chain(items.map(Some)).collect();
+    let mut equipped = vec![];
+    for defender in defenders {
+        for item in &items {
+            let mut defender = defender.clone();
+            slot(&mut defender, item.map(ItemInstance::new));
+            equipped.push(defender);
+        }
+    }
+
+    equipped
+}
+
+fn wearables_name(defender: &Combatant) -> String {
+    let mut names = vec![];
+    if let Some(bodywear) = &defender.bodywear {
+        names.push(<&Bodywear>::from(bodywear.base).display_name.as_str());
+    }
+    if let Some(footwear) = &defender.footwear {
+        names.push(<&Footwear>::from(footwear.base).display_name.as_str());
+    }
+    if let Some(handwear) = &defender.handwear {
+        names.push(<&Handwear>::from(handwear.base).display_name.as_str());
+    }
+    if let Some(headwear) = &defender.headwear {
+        names.push(<&Headwear>::from(headwear.base).display_name.as_str());
+    }
+    if let Some(legwear) = &defender.legwear {
+        names.push(<&Legwear>::from(legwear.base).display_name.as_str());
+    }
+
+    match names.is_empty() {
+        true => "Unarmored".into(),
+        false => names.join(" + "),
+    }
+}
+
+/// The hp a single target loses to the source, damage over time counted for every turn it lasts
+///
+/// Damage is worked out the way combat does, raw damage from the attacker's attributes and weapon scaling times the
+/// multiplier, then mitigated by the defender's defense threshold and absorbtion on every hit and every tick
+fn expected_damage(attacker: &Combatant, defender: &Combatant, source: &Source, horizon: u32) -> f64 {
+    let raw_damage = |aspect| {
+        let scaling = source.weapon.map_or(0., |weapon| <&Weapon>::from(weapon).scaling_bonus(attacker));
+        attacker.raw_damage(aspect) + scaling
+    };
+
+    source
+        .sub_effects
+        .iter()
+        .map(|sub_effect| match sub_effect {
+            SubEffect::Damage { aspect, multiplier } => defender.mitigate(*aspect, raw_damage(*aspect) * multiplier),
+            SubEffect::DOT { aspect, multiplier, lifetime } => {
+                let turns = match lifetime {
+                    Lifetime::Active { duration } => *duration,
+                    Lifetime::Constant => horizon,
+                };
+                defender.mitigate(*aspect, raw_damage(*aspect) * multiplier) * turns as f64
+            },
+            SubEffect::Cleanse { .. } | SubEffect::Modifier { .. } => 0.,
+        })
+        .sum()
+}
+
+fn csv(columns: &[&str], rows: &[(String, Vec<f64>)]) -> String {
+    let field = |value: &str| match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.into(),
+    };
+
+    let mut table = String::new();
+    let header: Vec<String> = iter::once("Wearables").chain(columns.iter().copied()).map(field).collect();
+    writeln!(table, "{}", header.join(",")).unwrap();
+    for (name, damage) in rows {
+        let values: Vec<String> = damage.iter().map(|damage| format!("{:.2}", damage)).collect();
+        writeln!(table, "{},{}", field(name), values.join(",")).unwrap();
+    }
+
+    table
+}
+
+fn markdown(columns: &[&str], rows: &[(String, Vec<f64>)]) -> String {
+    let cell = |value: &str| value.replace('|', "\\|");
+
+    let mut table = String::new();
+    let header: Vec<String> = iter::once("Wearables").chain(columns.iter().copied()).map(cell).collect();
+    writeln!(table, "| {} |", header.join(" | ")).unwrap();
+    writeln!(table, "|---{}|", "|--:".repeat(columns.len())).unwrap();
+    for (name, damage) in rows {
+        let values: Vec<String> = damage.iter().map(|damage| format!("{:.2}", damage)).collect();
+        writeln!(table, "| {} | {} |", cell(name), values.join(" | ")).unwrap();
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::defenders;
+    use super::expected_damage;
+    use super::reference_combatant;
+    use super::sources;
+    use super::wearables_name;
+
+    fn cell(source: &str, wearables: &str) -> f64 {
+        let attacker = reference_combatant("Attacker", |_| 10.);
+        let source = sources().into_iter().find(|candidate| candidate.name == source).unwrap();
+        let defender = defenders(reference_combatant("Defender", |_| 10.))
+            .into_iter()
+            .find(|defender| wearables_name(defender) == wearables)
+            .unwrap();
+
+        expected_damage(&attacker, &defender, &source, 10)
+    }
+
+    #[test]
+    fn weapon_against_armor() {
+        // 10 strength plus grade C scaling of 4, times 1.5, less the longsleeve's 2 physical defense
+        assert_eq!(cell("Attack (Pipe Iron)", "Breakers Longsleeve"), 19.);
+        assert_eq!(cell("Attack (Pipe Iron)", "Unarmored"), 21.);
+    }
+
+    #[test]
+    fn damage_over_time_counts_every_tick() {
+        // 10 intelligence times 10 mind halved, times 0.861, for each of 3 turns
+        assert!((cell("Scorch", "Unarmored") - 129.15).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,575 @@
+// Copyright 2021 Chay Nabors.
+
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use colosseum_core::accessory::Accessory;
+use colosseum_core::accessory::AccessoryIdentifier;
+use colosseum_core::affix::Affix;
+use colosseum_core::affix::AffixIdentifier;
+use colosseum_core::archetype::Archetype;
+use colosseum_core::archetype::ArchetypeIdentifier;
+use colosseum_core::bodywear::Bodywear;
+use colosseum_core::bodywear::BodywearIdentifier;
+use colosseum_core::consumable::Consumable;
+use colosseum_core::consumable::ConsumableIdentifier;
+use colosseum_core::content;
+use colosseum_core::content::Content;
+use colosseum_core::content::ContentError;
+use colosseum_core::content::ContentFile;
+use colosseum_core::content::ContentFormat;
+use colosseum_core::content::Identifier;
+use colosseum_core::content::Registry;
+use colosseum_core::effect::Effect;
+use colosseum_core::effect::SubEffect;
+use colosseum_core::effect::TargetingScheme;
+use colosseum_core::encounter::Encounter;
+use colosseum_core::encounter::EncounterIdentifier;
+use colosseum_core::equipment_set::EquipmentSet;
+use colosseum_core::equipment_set::EquipmentSetIdentifier;
+use colosseum_core::footwear::Footwear;
+use colosseum_core::footwear::FootwearIdentifier;
+use colosseum_core::handwear::Handwear;
+use colosseum_core::handwear::HandwearIdentifier;
+use colosseum_core::headwear::Headwear;
+use colosseum_core::headwear::HeadwearIdentifier;
+use colosseum_core::item::Item;
+use colosseum_core::legwear::Legwear;
+use colosseum_core::legwear::LegwearIdentifier;
+use colosseum_core::lifetime::Lifetime;
+use colosseum_core::locale::Localized;
+use colosseum_core::locale::Localizer;
+use colosseum_core::locale::StringTable;
+use colosseum_core::locale::StringTableIdentifier;
+use colosseum_core::locale::DEFAULT_LOCALE;
+use colosseum_core::loot_table::LootTable;
+use colosseum_core::loot_table::LootTableIdentifier;
+use colosseum_core::modifier::Modifier;
+use colosseum_core::offhand::Offhand;
+use colosseum_core::offhand::OffhandIdentifier;
+use colosseum_core::offhand::OffhandKind;
+use colosseum_core::roster::Roster;
+use colosseum_core::roster::RosterIdentifier;
+use colosseum_core::skill::Skill;
+use colosseum_core::skill::SkillIdentifier;
+use colosseum_core::weapon::Weapon;
+use colosseum_core::weapon::WeaponIdentifier;
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem with a content file, located down to the line where possible
+struct Issue {
+    path: PathBuf,
+    line: Option<usize>,
+    severity: Severity,
+    message: String,
+}
+
+impl Issue {
+    fn from_error(error: &ContentError) -> Self {
+        let (path, line, message) = match error {
+            ContentError::Io { path, error } => (path.clone(), None, error.to_string()),
+            ContentError::Duplicate { path, other } => {
+                (path.clone(), None, format!("defines the same entry as {}", other.display()))
+            },
+            ContentError::Parse { path, error } => (path.clone(), error.line(), error.message()),
+            ContentError::UnknownCategory(path) => (path.clone(), None, "not in a known content category".into()),
+            error => (PathBuf::new(), None, error.to_string()),
+        };
+
+        Self { path, line, severity: Severity::Error, message }
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}: {}", self.path.display(), line, self.severity, self.message),
+            None => write!(f, "{}: {}: {}", self.path.display(), self.severity, self.message),
+        }
+    }
+}
+
+/// Collects the issues of a single file, finding the line of each from words of the file it concerns
+///
+/// Field names and values are written the same way in every format short of quoting, so the words are unquoted
+struct Lint<'a> {
+    file: &'a ContentFile,
+    issues: &'a mut Vec<Issue>,
+}
+
+impl<'a> Lint<'a> {
+    fn report(&mut self, severity: Severity, words: &[&str], message: String) {
+        let line = self
+            .file
+            .contents
+            .lines()
+            .position(|line| words.iter().all(|word| line.contains(word)))
+            .map(|index| index + 1);
+        self.issues.push(Issue { path: self.file.path.clone(), line, severity, message });
+    }
+
+    fn error(&mut self, words: &[&str], message: String) {
+        self.report(Severity::Error, words, message);
+    }
+
+    fn warning(&mut self, words: &[&str], message: String) {
+        self.report(Severity::Warning, words, message);
+    }
+
+    fn text(&mut self, display_name: &str, description: &str) {
+        if display_name.trim().is_empty() {
+            self.error(&["display_name"], "display name is empty".into());
+        }
+        if description.trim().is_empty() {
+            self.warning(&["description"], "description is missing".into());
+        }
+    }
+
+    fn effect(&mut self, effect: &Effect) {
+        if effect.sub_effects.is_empty() {
+            self.warning(&["sub_effects"], "effect has no sub effects and does nothing".into());
+        }
+
+        for sub_effect in &effect.sub_effects {
+            match sub_effect {
+                SubEffect::Damage { aspect, multiplier } | SubEffect::DOT { aspect, multiplier, .. } => {
+                    if !aspect.has_damage_formula() {
+                        self.error(
+                            &[&format!("{:?}", aspect)],
+                            format!("{:?} damage has no formula yet and panics", aspect),
+                        );
+                    }
+                    if *multiplier <= 0. {
+                        self.warning(&["multiplier"], format!("a multiplier of {} never deals damage", multiplier));
+                    }
+                },
+                SubEffect::Cleanse { .. } => (),
+                SubEffect::Modifier { modifier, .. } => self.modifier(modifier),
+            }
+
+            if let SubEffect::DOT { lifetime: Lifetime::Active { duration: 0 }, .. } = sub_effect {
+                self.warning(&["duration"], "damage over time with a duration of 0 still ticks once".into());
+            }
+        }
+
+        if let Some(TargetingScheme::MultiTarget(0)) = effect.targeting_scheme {
+            self.error(&["MultiTarget"], "MultiTarget(0) never targets anyone".into());
+        }
+
+        if effect.target_flags.is_empty() {
+            self.warning(&["target_flags"], "target flags are empty, so anyone can be targeted".into());
+        } else if effect.target_flags.iter().any(|group| group.is_empty()) {
+            self.warning(&["target_flags"], "an empty group of target flags lets anyone be targeted".into());
+        }
+    }
+
+    fn modifier(&mut self, modifier: &Modifier) {
+        if let Lifetime::Active { duration: 0 } = modifier.lifetime {
+            self.warning(&["duration"], "a modifier with a duration of 0 expires the turn it is applied".into());
+        }
+    }
+}
+
+pub fn run(directories: &[PathBuf]) -> anyhow::Result<()> {
+    let (registry, errors) = Registry::load_lenient(directories);
+    let mut issues: Vec<Issue> = errors.iter().map(Issue::from_error).collect();
+    let registry = content::install(registry);
+
+    for directory in directories {
+        unloaded_files(directory, &mut issues);
+    }
+    lint(registry, &mut issues);
+    unused(registry, &mut issues);
+    translations(registry, &mut issues);
+
+    issues.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    for issue in &issues {
+        println!("{}", issue);
+    }
+
+    let errors = issues.iter().filter(|issue| issue.severity == Severity::Error).count();
+    let warnings = issues.len() - errors;
+    println!("Checked {} content files: {} errors, {} warnings", registry.files().len(), errors, warnings);
+
+    if errors > 0 {
+        bail!("content has {} errors", errors);
+    }
+
+    Ok(())
+}
+
+/// Files in a content directory the loader skips over, since they aren't content in a category directory
+fn unloaded_files(directory: &Path, issues: &mut Vec<Issue>) {
+    let entries = |directory: &Path| -> Vec<PathBuf> {
+        std::fs::read_dir(directory).into_iter().flatten().flatten().map(|entry| entry.path()).collect()
+    };
+
+    for path in entries(directory) {
+        let paths = match path.is_dir() {
+            true => entries(&path),
+            false => vec![path],
+        };
+
+        for path in paths {
+            if path.is_dir() || ContentFormat::from_path(&path).is_none() {
+                let message = "not a content file, it is never loaded".into();
+                issues.push(Issue { path, line: None, severity: Severity::Warning, message });
+            } else if path.parent() == Some(directory) {
+                let message = "content files belong in a category directory, it is never loaded".into();
+                issues.push(Issue { path, line: None, severity: Severity::Warning, message });
+            }
+        }
+    }
+}
+
+fn lint(registry: &Registry, issues: &mut Vec<Issue>) {
+    for accessory in AccessoryIdentifier::iter() {
+        let (mut lint, accessory) = entry(registry, accessory, issues);
+        lint.text(&accessory.display_name, &accessory.description);
+        for attribute_modifier in &accessory.modifiers {
+            lint.modifier(&attribute_modifier.modifier);
+        }
+    }
+
+    for affix in AffixIdentifier::iter() {
+        let (mut lint, affix) = entry(registry, affix, issues);
+        lint.text(&affix.display_name, &affix.description);
+        if affix.min > affix.max {
+            lint.error(&["min"], format!("min of {} is greater than max of {}", affix.min, affix.max));
+        }
+        if affix.slots.is_empty() {
+            lint.warning(&["slots"], "affix has no slots and is unused, it never rolls".into());
+        } else if affix.weight == 0 {
+            lint.warning(&["weight"], "affix has a weight of 0 and is unused, it never rolls".into());
+        }
+    }
+
+    for consumable in ConsumableIdentifier::iter() {
+        let (mut lint, consumable) = entry(registry, consumable, issues);
+        lint.text(&consumable.display_name, &consumable.description);
+        lint.effect(&consumable.effect);
+        if consumable.max_count == 0 {
+            lint.warning(&["max_count"], "a max count of 0 means the consumable is never carried".into());
+        }
+    }
+
+    for equipment_set in EquipmentSetIdentifier::iter() {
+        let (mut lint, equipment_set) = entry(registry, equipment_set, issues);
+        lint.text(&equipment_set.display_name, &equipment_set.description);
+        for bonus in &equipment_set.bonuses {
+            if bonus.pieces > equipment_set.members.len() {
+                let message = format!(
+                    "bonus for {} pieces is unused, the set only has {} members",
+                    bonus.pieces,
+                    equipment_set.members.len()
+                );
+                lint.warning(&["pieces", &bonus.pieces.to_string()], message);
+            }
+            for attribute_modifier in &bonus.modifiers {
+                lint.modifier(&attribute_modifier.modifier);
+            }
+        }
+    }
+
+    for offhand in OffhandIdentifier::iter() {
+        let (mut lint, offhand) = entry(registry, offhand, issues);
+        lint.text(&offhand.display_name, &offhand.description);
+        if let OffhandKind::Shield { block_chance } = offhand.kind {
+            if !(0. ..=1.).contains(&block_chance) {
+                lint.warning(&["block_chance"], format!("block chance of {} is clamped between 0 and 1", block_chance));
+            }
+        }
+    }
+
+    for skill in SkillIdentifier::iter() {
+        let (mut lint, skill) = entry(registry, skill, issues);
+        lint.text(&skill.display_name, &skill.description);
+        lint.effect(&skill.effect);
+    }
+
+    for weapon in WeaponIdentifier::iter() {
+        let (mut lint, weapon) = entry(registry, weapon, issues);
+        lint.text(&weapon.display_name, &weapon.description);
+        lint.effect(&weapon.effect);
+    }
+
+    for bodywear in BodywearIdentifier::iter() {
+        let (mut lint, bodywear) = entry(registry, bodywear, issues);
+        lint.text(&bodywear.display_name, &bodywear.description);
+    }
+    for footwear in FootwearIdentifier::iter() {
+        let (mut lint, footwear) = entry(registry, footwear, issues);
+        lint.text(&footwear.display_name, &footwear.description);
+    }
+    for handwear in HandwearIdentifier::iter() {
+        let (mut lint, handwear) = entry(registry, handwear, issues);
+        lint.text(&handwear.display_name, &handwear.description);
+    }
+    for headwear in HeadwearIdentifier::iter() {
+        let (mut lint, headwear) = entry(registry, headwear, issues);
+        lint.text(&headwear.display_name, &headwear.description);
+    }
+    for legwear in LegwearIdentifier::iter() {
+        let (mut lint, legwear) = entry(registry, legwear, issues);
+        lint.text(&legwear.display_name, &legwear.description);
+    }
+
+    for archetype in ArchetypeIdentifier::iter() {
+        let (mut lint, archetype) = entry(registry, archetype, issues);
+        if archetype.name.trim().is_empty() {
+            lint.error(&["name"], "name is empty".into());
+        }
+        if archetype.vigor <= 0. {
+            lint.error(&["vigor"], format!("a vigor of {} means the combatant enters battle dead", archetype.vigor));
+        }
+        if archetype.skills.is_empty() {
+            lint.warning(&["skills"], "combatant has no skills".into());
+        }
+    }
+
+    for roster in RosterIdentifier::iter() {
+        let (mut lint, roster) = entry(registry, roster, issues);
+        lint.text(&roster.display_name, &roster.description);
+        if let Err(e) = roster.party().validate() {
+            lint.error(&["members"], format!("party can't enter combat: {}", e));
+        }
+    }
+
+    for encounter in EncounterIdentifier::iter() {
+        let (mut lint, encounter) = entry(registry, encounter, issues);
+        lint.text(&encounter.display_name, &encounter.description);
+        if encounter.enemies.is_empty() {
+            lint.error(&["enemies"], "encounter has no enemies, it's won before it starts".into());
+        }
+        for attribute_modifier in &encounter.arena_modifiers {
+            lint.modifier(&attribute_modifier.modifier);
+        }
+    }
+
+    for loot_table in LootTableIdentifier::iter() {
+        let (mut lint, loot_table) = entry(registry, loot_table, issues);
+        if loot_table.drops.iter().all(|drop| drop.weight == 0) {
+            lint.warning(&["drops"], "loot table has no drops with any weight, it never drops anything".into());
+        } else if loot_table.rolls == 0 {
+            lint.warning(&["rolls"], "a roll count of 0 means the loot table never drops anything".into());
+        }
+    }
+
+    let keys = registry.keys();
+    for table in StringTableIdentifier::iter() {
+        let (mut lint, table) = entry(registry, table, issues);
+        if table.locale.trim().is_empty() {
+            lint.error(&["locale"], "locale is empty".into());
+        }
+        for (category, entries) in &table.content {
+            for key in entries.keys() {
+                if !keys.contains(&(category.as_str(), key.as_str())) {
+                    lint.warning(&[key], format!("translates {} '{}', which doesn't exist", category, key));
+                }
+            }
+        }
+    }
+}
+
+/// Reports entries nothing plays with, as no archetype, roster, encounter or loot table refers to them
+fn unused(registry: &Registry, issues: &mut Vec<Issue>) {
+    let mut used = BTreeSet::new();
+    for roster in registry.rosters.identifiers().filter_map(|roster| registry.rosters.get(roster)) {
+        used.extend(roster.members.iter().map(|member| key(*member)));
+        used.extend(roster.inventory.iter().map(|item| item_key(item.base)));
+    }
+    for encounter in registry.encounters.identifiers().filter_map(|encounter| registry.encounters.get(encounter)) {
+        used.extend(encounter.enemies.iter().map(|enemy| key(*enemy)));
+        used.extend(encounter.loot.map(key));
+    }
+    for loot_table in registry.loot_tables.identifiers().filter_map(|loot_table| registry.loot_tables.get(loot_table)) {
+        used.extend(loot_table.drops.iter().map(|drop| item_key(drop.item)));
+    }
+    for archetype in registry.combatants.identifiers().filter_map(|archetype| registry.combatants.get(archetype)) {
+        used.extend(archetype.skills.iter().map(|skill| key(*skill)));
+        let equipment = [
+            archetype.accessory.as_ref().map(|item| key(item.base)),
+            archetype.bodywear.as_ref().map(|item| key(item.base)),
+            archetype.footwear.as_ref().map(|item| key(item.base)),
+            archetype.handwear.as_ref().map(|item| key(item.base)),
+            archetype.headwear.as_ref().map(|item| key(item.base)),
+            archetype.legwear.as_ref().map(|item| key(item.base)),
+            archetype.offhand.as_ref().map(|item| key(item.base)),
+            archetype.weapon.as_ref().map(|item| key(item.base)),
+        ];
+        used.extend(equipment.into_iter().flatten());
+    }
+    for offhand in registry.offhands.identifiers().filter_map(|offhand| registry.offhands.get(offhand)) {
+        if let OffhandKind::Weapon(weapon) = offhand.kind {
+            used.insert(key(weapon));
+        }
+    }
+
+    let referable = [
+        Accessory::CATEGORY,
+        Archetype::CATEGORY,
+        Bodywear::CATEGORY,
+        Consumable::CATEGORY,
+        Footwear::CATEGORY,
+        Handwear::CATEGORY,
+        Headwear::CATEGORY,
+        Legwear::CATEGORY,
+        LootTable::CATEGORY,
+        Offhand::CATEGORY,
+        Skill::CATEGORY,
+        Weapon::CATEGORY,
+    ];
+    for file in registry.files() {
+        if referable.contains(&file.category.as_str()) && !used.contains(&(file.category.as_str(), file.key.as_str())) {
+            let message = format!(
+                "{} '{}' is unused, no archetype, roster, encounter or loot table refers to it",
+                file.category, file.key
+            );
+            issues.push(Issue { path: file.path.clone(), line: None, severity: Severity::Warning, message });
+        }
+    }
+}
+
+fn key<T: Content>(identifier: Identifier<T>) -> (&'static str, &'static str) {
+    (T::CATEGORY, identifier.key())
+}
+
+fn item_key(item: Item) -> (&'static str, &'static str) {
+    match item {
+        Item::Accessory(accessory) => key(accessory),
+        Item::Bodywear(bodywear) => key(bodywear),
+        Item::Consumable(consumable) => key(consumable),
+        Item::Footwear(footwear) => key(footwear),
+        Item::Handwear(handwear) => key(handwear),
+        Item::Headwear(headwear) => key(headwear),
+        Item::Legwear(legwear) => key(legwear),
+        Item::Offhand(offhand) => key(offhand),
+        Item::Weapon(weapon) => key(weapon),
+    }
+}
+
+/// Reports every text a locale falls back to the default locale for, on the first table of the locale
+fn translations(registry: &Registry, issues: &mut Vec<Issue>) {
+    let tables: Vec<(&PathBuf, &StringTable)> = StringTableIdentifier::iter()
+        .map(|table| {
+            let path = &registry
+                .files()
+                .iter()
+                .find(|file| file.category == "locale" && file.key == table.key())
+                .unwrap()
+                .path;
+            (path, registry.locales.get(table).unwrap())
+        })
+        .collect();
+    let locales: BTreeSet<&str> =
+        tables.iter().map(|(_, table)| table.locale.as_str()).filter(|locale| *locale != DEFAULT_LOCALE).collect();
+    let interface: BTreeSet<&str> =
+        tables.iter().flat_map(|(_, table)| table.interface.keys().map(String::as_str)).collect();
+
+    for locale in locales {
+        let localizer = Localizer::new(locale);
+        let translating: Vec<&StringTable> = tables
+            .iter()
+            .filter(|(_, table)| table.locale != DEFAULT_LOCALE && localizer.locales().contains(&table.locale))
+            .map(|(_, table)| *table)
+            .collect();
+        let path = tables.iter().find(|(_, table)| table.locale == locale).unwrap().0;
+
+        let mut missing = vec![];
+        untranslated::<Accessory>(registry, &translating, &mut missing);
+        untranslated::<Affix>(registry, &translating, &mut missing);
+        untranslated::<Bodywear>(registry, &translating, &mut missing);
+        untranslated::<Consumable>(registry, &translating, &mut missing);
+        untranslated::<Encounter>(registry, &translating, &mut missing);
+        untranslated::<EquipmentSet>(registry, &translating, &mut missing);
+        untranslated::<Footwear>(registry, &translating, &mut missing);
+        untranslated::<Handwear>(registry, &translating, &mut missing);
+        untranslated::<Headwear>(registry, &translating, &mut missing);
+        untranslated::<Legwear>(registry, &translating, &mut missing);
+        untranslated::<Offhand>(registry, &translating, &mut missing);
+        untranslated::<Roster>(registry, &translating, &mut missing);
+        untranslated::<Skill>(registry, &translating, &mut missing);
+        untranslated::<Weapon>(registry, &translating, &mut missing);
+        for id in &interface {
+            if !translating.iter().any(|table| table.interface.contains_key(*id)) {
+                missing.push(format!("interface text '{}'", id));
+            }
+        }
+
+        for text in missing {
+            let message = format!("no {} translation of {}", locale, text);
+            issues.push(Issue { path: path.clone(), line: None, severity: Severity::Warning, message });
+        }
+    }
+}
+
+/// The text of every entry of a kind the tables have no translation for
+fn untranslated<T: Localized>(registry: &Registry, tables: &[&StringTable], missing: &mut Vec<String>) {
+    for identifier in Identifier::<T>::iter() {
+        let entry = T::store(registry).get(identifier).unwrap();
+        let texts: Vec<_> =
+            tables.iter().filter_map(|table| table.content.get(T::CATEGORY)?.get(identifier.key())).collect();
+
+        if !texts.iter().any(|text| text.display_name.is_some()) {
+            missing.push(format!("{} '{}' display name", T::CATEGORY, identifier));
+        }
+        if !entry.description().trim().is_empty() && !texts.iter().any(|text| text.description.is_some()) {
+            missing.push(format!("{} '{}' description", T::CATEGORY, identifier));
+        }
+    }
+}
+
+/// An entry of the registry along with a lint for the file it was loaded from
+fn entry<'a, T: Content>(
+    registry: &'a Registry,
+    identifier: Identifier<T>,
+    issues: &'a mut Vec<Issue>,
+) -> (Lint<'a>, &'a T) {
+    let file = registry
+        .files()
+        .iter()
+        .find(|file| file.category == T::CATEGORY && file.key == identifier.key())
+        .expect("every entry is loaded from a file");
+
+    (Lint { file, issues }, T::store(registry).get(identifier).unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use colosseum_core::content::ContentFile;
+    use colosseum_core::content::Registry;
+
+    use super::unused;
+
+    #[test]
+    fn unreferenced_entries_are_unused() {
+        let mut files = Registry::embedded().unwrap().files().to_vec();
+        let sweep = files.iter().find(|file| file.category == "skill" && file.key == "sweep").unwrap().clone();
+        files.push(ContentFile { key: "ember".into(), path: "skill/ember.json".into(), ..sweep });
+        let registry = Registry::from_pushed(files).unwrap();
+
+        let mut issues = vec![];
+        unused(&registry, &mut issues);
+        let unused: Vec<&str> = issues.iter().map(|issue| issue.message.as_str()).collect();
+        assert!(unused.iter().any(|message| message.starts_with("skill 'ember' is unused")), "{:?}", unused);
+        assert!(!unused.iter().any(|message| message.starts_with("skill 'sweep'")), "{:?}", unused);
+        assert!(!unused.iter().any(|message| message.starts_with("weapon 'pipe_iron'")), "{:?}", unused);
+    }
+}
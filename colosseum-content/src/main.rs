@@ -0,0 +1,49 @@
+// Copyright 2021 Chay Nabors.
+
+mod balance;
+mod check;
+mod schema;
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use clap::Subcommand;
+use colosseum_core::content;
+use colosseum_core::content::Registry;
+
+/// Tools for working with Colosseum content
+#[derive(Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Content directories laid over the embedded content, later directories take precedence
+    #[arg(long, global = true)]
+    content: Vec<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Tabulates the expected damage of every weapon, skill and consumable against every combination of wearables
+    Balance(balance::BalanceArgs),
+    /// Reports every content file that fails to load, and content that loads but is broken or unused
+    Check,
+    /// Writes a JSON Schema for the files of every content category, for editors to complete and validate against
+    Schema(schema::SchemaArgs),
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    match args.command {
+        Command::Balance(balance_args) => {
+            content::install(Registry::load(&args.content)?);
+            balance::run(balance_args)
+        },
+        Command::Check => check::run(&args.content),
+        Command::Schema(schema_args) => {
+            content::install(Registry::load(&args.content)?);
+            schema::run(schema_args)
+        },
+    }
+}
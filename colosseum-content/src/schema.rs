@@ -0,0 +1,27 @@
+// Copyright 2021 Chay Nabors.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use colosseum_core::content;
+
+#[derive(clap::Args)]
+pub struct SchemaArgs {
+    /// The directory each `<category>.schema.json` is written to
+    #[arg(short, long, default_value = "schema")]
+    output: PathBuf,
+}
+
+pub fn run(args: SchemaArgs) -> anyhow::Result<()> {
+    fs::create_dir_all(&args.output).with_context(|| format!("creating {:?}", args.output))?;
+
+    for (category, schema) in content::schemas() {
+        let path = args.output.join(format!("{}.schema.json", category));
+        let json = serde_json::to_string_pretty(&schema)?;
+        fs::write(&path, json + "\n").with_context(|| format!("writing {:?}", path))?;
+        println!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+// Copyright 2021 Chay Nabors.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::content::Identifier;
+use crate::modifier::AttributeModifier;
+
+pub type AccessoryIdentifier = Identifier<Accessory>;
+
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
+pub struct Accessory {
+    pub display_name: String,
+    pub description: String,
+    pub modifiers: Vec<AttributeModifier>,
+}
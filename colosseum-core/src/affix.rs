@@ -0,0 +1,83 @@
+// Copyright 2021 Chay Nabors.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::aspect::Aspect;
+use crate::attribute::Attribute;
+use crate::content::Identifier;
+use crate::item::EquipmentSlot;
+use crate::item::Rarity;
+
+pub type AffixIdentifier = Identifier<Affix>;
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub enum AffixKind {
+    /// Adds the rolled value to an attribute
+    Attribute(Attribute),
+    /// Adds the rolled value to defense against an aspect
+    Defense(Aspect),
+    /// Adds the rolled value, as a fraction of damage, to absorbtion of an aspect
+    Absorption(Aspect),
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Affix {
+    pub display_name: String,
+    pub description: String,
+    /// The slots whose affix pools include this affix
+    pub slots: Vec<EquipmentSlot>,
+    /// Relative likelihood of being rolled against the rest of the pool
+    pub weight: u32,
+    #[serde(default)]
+    pub minimum_rarity: Rarity,
+    pub kind: AffixKind,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct RolledAffix {
+    pub affix: AffixIdentifier,
+    pub value: f64,
+}
+
+impl RolledAffix {
+    pub fn kind(&self) -> AffixKind {
+        <&Affix>::from(self.affix).kind
+    }
+}
+
+/// Every affix that can roll on an item in the slot at the rarity
+pub fn pool(slot: EquipmentSlot, rarity: Rarity) -> Vec<AffixIdentifier> {
+    AffixIdentifier::iter()
+        .filter(|identifier| {
+            let affix = <&Affix>::from(*identifier);
+            affix.slots.contains(&slot) && affix.minimum_rarity <= rarity && affix.weight > 0
+        })
+        .collect()
+}
+
+/// Rolls the rarity's number of distinct affixes from the slot's pool
+pub fn roll<R: Rng>(slot: EquipmentSlot, rarity: Rarity, rng: &mut R) -> Vec<RolledAffix> {
+    let mut pool = pool(slot, rarity);
+    let mut affixes = vec![];
+
+    while affixes.len() < rarity.affix_count() {
+        let identifier = match pool.choose_weighted(rng, |identifier| <&Affix>::from(*identifier).weight) {
+            Ok(identifier) => *identifier,
+            Err(_) => break,
+        };
+
+        pool.retain(|other| *other != identifier);
+
+        let affix = <&Affix>::from(identifier);
+        let value = if affix.max > affix.min { rng.gen_range(affix.min..=affix.max) } else { affix.min };
+        affixes.push(RolledAffix { affix: identifier, value });
+    }
+
+    affixes
+}
@@ -0,0 +1,97 @@
+// Copyright 2021 Chay Nabors.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::accessory::AccessoryIdentifier;
+use crate::bodywear::BodywearIdentifier;
+use crate::combatant::Combatant;
+use crate::content::Identifier;
+use crate::footwear::FootwearIdentifier;
+use crate::gender::Gender;
+use crate::handwear::HandwearIdentifier;
+use crate::headwear::HeadwearIdentifier;
+use crate::item::ItemInstance;
+use crate::legwear::LegwearIdentifier;
+use crate::offhand::OffhandIdentifier;
+use crate::skill::SkillIdentifier;
+use crate::weapon::WeaponIdentifier;
+
+pub type ArchetypeIdentifier = Identifier<Archetype>;
+
+/// A combatant as it enters its first battle, with its base attributes, skills and equipment
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Archetype {
+    pub name: String,
+    pub gender: Gender,
+    #[serde(default)]
+    pub skills: Vec<SkillIdentifier>,
+
+    pub agility: f64,
+    pub dexterity: f64,
+    pub intelligence: f64,
+    pub mind: f64,
+    pub strength: f64,
+    pub vigor: f64,
+    pub vitality: f64,
+
+    #[serde(default)]
+    pub bodywear: Option<ItemInstance<BodywearIdentifier>>,
+    #[serde(default)]
+    pub footwear: Option<ItemInstance<FootwearIdentifier>>,
+    #[serde(default)]
+    pub handwear: Option<ItemInstance<HandwearIdentifier>>,
+    #[serde(default)]
+    pub headwear: Option<ItemInstance<HeadwearIdentifier>>,
+    #[serde(default)]
+    pub legwear: Option<ItemInstance<LegwearIdentifier>>,
+    #[serde(default)]
+    pub weapon: Option<ItemInstance<WeaponIdentifier>>,
+    #[serde(default)]
+    pub offhand: Option<ItemInstance<OffhandIdentifier>>,
+    #[serde(default)]
+    pub accessory: Option<ItemInstance<AccessoryIdentifier>>,
+}
+
+impl Archetype {
+    /// A combatant of the archetype at full hp, without modifiers or damage over time
+    pub fn combatant(&self) -> Combatant {
+        let mut combatant = Combatant {
+            name: self.name.clone(),
+            gender: self.gender,
+            skills: self.skills.clone(),
+
+            agility: self.agility,
+            dexterity: self.dexterity,
+            intelligence: self.intelligence,
+            mind: self.mind,
+            strength: self.strength,
+            vigor: self.vigor,
+            vitality: self.vitality,
+
+            bodywear: self.bodywear.clone(),
+            footwear: self.footwear.clone(),
+            handwear: self.handwear.clone(),
+            headwear: self.headwear.clone(),
+            legwear: self.legwear.clone(),
+            weapon: self.weapon.clone(),
+            offhand: self.offhand.clone(),
+            accessory: self.accessory.clone(),
+
+            hp: 0.,
+            fatigue: f64::MAX,
+            dots: vec![],
+
+            agility_modifiers: vec![],
+            dexterity_modifiers: vec![],
+            intelligence_modifiers: vec![],
+            mind_modifiers: vec![],
+            strength_modifiers: vec![],
+            vigor_modifiers: vec![],
+            vitality_modifiers: vec![],
+        };
+        combatant.hp = combatant.hp_max();
+        combatant
+    }
+}
@@ -1,12 +1,20 @@
 // Copyright 2021 Chay Nabors.
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
 pub enum Aspect {
     Fire,
     Frost,
     Lightning,
     Physical,
 }
+
+impl Aspect {
+    /// Whether combatants have a formula for raw damage of the aspect, dealing damage of any other aspect panics
+    pub fn has_damage_formula(&self) -> bool {
+        matches!(self, Aspect::Fire | Aspect::Physical)
+    }
+}
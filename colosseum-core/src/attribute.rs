@@ -1,9 +1,11 @@
 // Copyright 2021 Chay Nabors.
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
+use strum::EnumIter;
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, EnumIter, Eq, JsonSchema, PartialEq, Serialize)]
 pub enum Attribute {
     Agility,
     Dexterity,
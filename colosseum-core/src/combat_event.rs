@@ -7,10 +7,22 @@ use crate::consumable::ConsumableIdentifier;
 use crate::skill::SkillIdentifier;
 use crate::target::Target;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum CombatEvent {
     AttackEvent { source: Target, targets: Vec<Target> },
     ConsumableEvent { source: Target, consumable: ConsumableIdentifier, targets: Vec<Target> },
     SkillEvent { source: Target, skill: SkillIdentifier, targets: Vec<Target> },
     SkipEvent,
 }
+
+impl CombatEvent {
+    /// The combatant performing the event, skipping names nobody
+    pub fn source(&self) -> Option<Target> {
+        match self {
+            CombatEvent::AttackEvent { source, .. } => Some(*source),
+            CombatEvent::ConsumableEvent { source, .. } => Some(*source),
+            CombatEvent::SkillEvent { source, .. } => Some(*source),
+            CombatEvent::SkipEvent => None,
+        }
+    }
+}
@@ -1,7 +1,11 @@
 // Copyright 2021 Chay Nabors.
 
+use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
 use serde::Deserialize;
 use serde::Serialize;
+use strum::IntoEnumIterator;
 
 use crate::aspect::Aspect;
 use crate::attribute::Attribute;
@@ -10,102 +14,150 @@ use crate::combatant::Combatant;
 use crate::consumable::Consumable;
 use crate::dot::DOT;
 use crate::effect::SubEffect;
-use crate::lifetime::Lifetime;
+use crate::modifier::ModifierOrigin;
+use crate::modifier::ModifierSource;
 use crate::party::Party;
 use crate::skill::Skill;
 use crate::target::Target;
 use crate::weapon::Weapon;
+use crate::weapon::WeaponIdentifier;
+
+/// The effect of an attack made without a weapon
+pub const UNARMED_SUB_EFFECT: SubEffect = SubEffect::Damage { aspect: Aspect::Physical, multiplier: 1. };
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+pub(crate) const FNV_PRIME: u64 = 0x100000001b3;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CombatState {
     pub parties: Vec<Party>,
+    /// Shared by every participant so that chance-based outcomes resolve identically everywhere
+    pub rng: Pcg32,
 }
 
 impl CombatState {
+    pub fn new(parties: Vec<Party>, seed: u64) -> Self {
+        Self { parties, rng: Pcg32::seed_from_u64(seed) }
+    }
+
+    /// Applies an event and then advances lasting effects by a turn
     pub fn process_event(&mut self, combat_event: &CombatEvent) {
+        self.apply_event(combat_event);
+        self.tick();
+    }
+
+    /// Applies an event's effects without advancing lasting effects
+    pub fn apply_event(&mut self, combat_event: &CombatEvent) {
         use CombatEvent::*;
         match combat_event {
             AttackEvent { source, targets } => {
                 for target in targets {
+                    let block_chance = self.parties[target.party_index].members[target.member_index].block_chance();
+                    if block_chance > 0. && self.rng.gen_bool(block_chance) {
+                        continue;
+                    }
+
+                    let applier = *source;
                     let (source, target) = self.get_combatant_handles(*source, *target);
-                    let weapon = match source {
-                        None => target.weapon,
-                        Some(source) => source.weapon,
+                    let (weapon, offhand_weapon) = match source {
+                        None => (target.weapon_identifier(), target.offhand_weapon()),
+                        Some(source) => (source.weapon_identifier(), source.offhand_weapon()),
                     };
 
                     match weapon {
-                        None => {
-                            handle_sub_effect(source, target, SubEffect::Damage { aspect: Aspect::Physical, multiplier: 1. })
-                        },
-                        Some(weapon) => {
-                            for sub_effect in &<&Weapon>::from(weapon).effect.sub_effects {
-                                handle_sub_effect(source, target, *sub_effect);
-                            }
-                        },
+                        None => handle_sub_effect(source, target, &UNARMED_SUB_EFFECT, None),
+                        Some(weapon) => handle_weapon(source, target, weapon, applier),
+                    }
+
+                    if let Some(offhand_weapon) = offhand_weapon {
+                        handle_weapon(source, target, offhand_weapon, applier);
                     }
                 }
             },
             ConsumableEvent { source, targets, consumable } => {
+                let modifier_source =
+                    ModifierSource { origin: ModifierOrigin::Consumable(*consumable), applier: *source };
                 let consumable = <&Consumable>::from(*consumable);
                 for target in targets {
                     let (source, target) = self.get_combatant_handles(*source, *target);
                     for sub_effect in &consumable.effect.sub_effects {
-                        handle_sub_effect(source, target, *sub_effect);
+                        handle_sub_effect(source, target, sub_effect, Some(modifier_source));
                     }
                 }
             },
             SkillEvent { source, targets, skill } => {
+                let modifier_source = ModifierSource { origin: ModifierOrigin::Skill(*skill), applier: *source };
                 for target in targets {
                     let (source, target) = self.get_combatant_handles(*source, *target);
                     let skill = <&Skill>::from(*skill);
                     for sub_effect in &skill.effect.sub_effects {
-                        handle_sub_effect(source, target, *sub_effect);
+                        handle_sub_effect(source, target, sub_effect, Some(modifier_source));
                     }
                 }
             },
             SkipEvent => (),
         }
+    }
 
+    /// Deals damage over time and counts down the lifetimes of damage over time and modifiers
+    pub fn tick(&mut self) {
         for party in &mut self.parties {
             for combatant in &mut party.members {
                 for i in 0..combatant.dots.len() {
-                    process_damage(combatant, combatant.dots[i].aspect, combatant.dots[i].damage_value);
-                    match combatant.dots[i].lifetime {
-                        Lifetime::Active { ref mut duration } => {
-                            if *duration > 0 {
-                                *duration -= 1
-                            }
-                        },
-                        Lifetime::Constant => (),
+                    if combatant.dots[i].lifetime.expired() {
+                        continue;
                     }
-                }
 
-                macro_rules! update_modifiers {
-                    ($modifiers:ident) => {
-                        for modifier in &mut combatant.$modifiers {
-                            match modifier.lifetime {
-                                Lifetime::Active { ref mut duration } => {
-                                    if *duration > 0 {
-                                        *duration -= 1
-                                    }
-                                },
-                                Lifetime::Constant => (),
-                            }
-                        }
-                    };
+                    process_damage(combatant, combatant.dots[i].aspect, combatant.dots[i].damage_value);
+                    combatant.dots[i].lifetime.tick();
                 }
+                combatant.dots.retain(|dot| !dot.lifetime.expired());
 
-                update_modifiers!(agility_modifiers);
-                update_modifiers!(dexterity_modifiers);
-                update_modifiers!(intelligence_modifiers);
-                update_modifiers!(mind_modifiers);
-                update_modifiers!(strength_modifiers);
-                update_modifiers!(vigor_modifiers);
-                update_modifiers!(vitality_modifiers);
+                for attribute in Attribute::iter() {
+                    let modifiers = combatant.modifiers_mut(attribute);
+                    for modifier in modifiers.iter_mut() {
+                        modifier.lifetime.tick();
+                    }
+
+                    modifiers.retain(|modifier| !modifier.lifetime.expired());
+                }
             }
         }
     }
 
+    /// A hash of the entire state that agrees across machines, floats are hashed by their exact bits
+    pub fn checksum(&self) -> u64 {
+        let bytes = bincode::serialize(self).expect("combat state failed to serialize");
+        bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME))
+    }
+
+    /// The indices of parties with at least one living member
+    pub fn living_parties(&self) -> Vec<usize> {
+        (0..self.parties.len())
+            .filter(|party_index| self.parties[*party_index].members.iter().any(|member| member.alive()))
+            .collect()
+    }
+
+    /// Combat ends once at most one party has living members, or once none of the living can act again
+    pub fn is_over(&self) -> bool {
+        self.living_parties().len() <= 1 || self.stalled()
+    }
+
+    fn stalled(&self) -> bool {
+        self.parties
+            .iter()
+            .flat_map(|party| &party.members)
+            .all(|member| !member.ready() && (member.dead() || member.attribute(Attribute::Agility) <= 0.))
+    }
+
+    /// The index of the last party standing, if combat is over and anyone survived
+    pub fn victor(&self) -> Option<usize> {
+        match self.living_parties()[..] {
+            [victor] => Some(victor),
+            _ => None,
+        }
+    }
+
     fn get_combatant_handles(&mut self, source: Target, target: Target) -> (Option<&Combatant>, &mut Combatant) {
         if source.party_index > target.party_index {
             let (target_container, source_container) = self.parties.split_at_mut(source.party_index);
@@ -149,7 +201,8 @@ impl CombatState {
         target_list
     }
 
-    pub fn next_combatant(&mut self) -> Target {
+    /// Readies the next combatant to act, none if no living combatant is ready or ever will be
+    pub fn next_combatant(&mut self) -> Option<Target> {
         loop {
             let mut readied = vec![];
 
@@ -163,19 +216,27 @@ impl CombatState {
 
             if let Some(ready) = readied.first() {
                 self.parties[ready.party_index].members[ready.member_index].fatigue = f64::MAX;
-                return *ready;
+                return Some(*ready);
             }
 
+            // the combatant that readies soonest has its fatigue zeroed outright, so rounding can't stall the loop
+            let mut soonest = None;
             let mut fatigue_agility_ratio = f64::MAX;
-            for party in &self.parties {
-                for member in &party.members {
-                    if member.alive() {
-                        fatigue_agility_ratio =
-                            fatigue_agility_ratio.min(member.fatigue / member.attribute(Attribute::Agility));
+            for party_index in 0..self.parties.len() {
+                for member_index in 0..self.parties[party_index].members.len() {
+                    let member = &self.parties[party_index].members[member_index];
+                    let agility = member.attribute(Attribute::Agility);
+                    if member.alive()
+                        && agility > 0.
+                        && (soonest.is_none() || member.fatigue / agility < fatigue_agility_ratio)
+                    {
+                        fatigue_agility_ratio = member.fatigue / agility;
+                        soonest = Some(Target { party_index, member_index });
                     }
                 }
             }
 
+            let soonest = soonest?;
             for party in &mut self.parties {
                 for member in &mut party.members {
                     if member.alive() {
@@ -184,52 +245,62 @@ impl CombatState {
                     }
                 }
             }
+
+            self.parties[soonest.party_index].members[soonest.member_index].fatigue = 0.;
         }
     }
 }
 
-fn handle_sub_effect(source: Option<&Combatant>, target: &mut Combatant, sub_effect: SubEffect) {
+fn handle_weapon(source: Option<&Combatant>, target: &mut Combatant, weapon: WeaponIdentifier, applier: Target) {
+    let modifier_source = ModifierSource { origin: ModifierOrigin::Weapon(weapon), applier };
+    for sub_effect in &<&Weapon>::from(weapon).effect.sub_effects {
+        handle_sub_effect(source, target, sub_effect, Some(modifier_source));
+    }
+}
+
+fn handle_sub_effect(
+    source: Option<&Combatant>,
+    target: &mut Combatant,
+    sub_effect: &SubEffect,
+    modifier_source: Option<ModifierSource>,
+) {
     match sub_effect {
+        SubEffect::Cleanse { tag } => target.cleanse(*tag),
         SubEffect::Damage { aspect, multiplier } => {
-            let damage_value = calculate_damage_value(source, target, aspect, multiplier);
-            process_damage(target, aspect, damage_value);
+            let damage_value = calculate_damage_value(source, target, *aspect, *multiplier, modifier_source);
+            process_damage(target, *aspect, damage_value);
         },
         SubEffect::DOT { aspect, multiplier, lifetime } => {
-            let damage_value = calculate_damage_value(source, target, aspect, multiplier);
+            let damage_value = calculate_damage_value(source, target, *aspect, *multiplier, modifier_source);
 
-            let dot = DOT { aspect, damage_value, lifetime };
+            let dot = DOT { aspect: *aspect, damage_value, lifetime: *lifetime };
 
             target.dots.push(dot);
         },
         SubEffect::Modifier { modifier, attribute } => {
-            use Attribute::*;
-            match attribute {
-                Agility => target.agility_modifiers.push(modifier),
-                Dexterity => target.dexterity_modifiers.push(modifier),
-                Intelligence => target.intelligence_modifiers.push(modifier),
-                Mind => target.mind_modifiers.push(modifier),
-                Strength => target.strength_modifiers.push(modifier),
-                Vigor => target.vigor_modifiers.push(modifier),
-                Vitality => target.vitality_modifiers.push(modifier),
-            }
+            let mut modifier = modifier.clone();
+            modifier.source = modifier_source;
+            target.apply_modifier(*attribute, modifier);
         },
     }
 }
 
-fn calculate_damage_value(source: Option<&Combatant>, target: &Combatant, aspect: Aspect, multiplier: f64) -> f64 {
-    let raw_damage = match source {
-        Some(source) => source.raw_damage(aspect),
-        None => target.raw_damage(aspect),
-    };
+fn calculate_damage_value(
+    source: Option<&Combatant>,
+    target: &Combatant,
+    aspect: Aspect,
+    multiplier: f64,
+    modifier_source: Option<ModifierSource>,
+) -> f64 {
+    let attacker = source.unwrap_or(target);
+    let mut raw_damage = attacker.raw_damage(aspect);
+    if let Some(ModifierSource { origin: ModifierOrigin::Weapon(weapon), .. }) = modifier_source {
+        raw_damage += <&Weapon>::from(weapon).scaling_bonus(attacker);
+    }
 
     raw_damage * multiplier
 }
 
 fn process_damage(target: &mut Combatant, aspect: Aspect, damage: f64) {
-    let defense = target.defense(aspect);
-    let absorbtion = target.absorbtion(aspect);
-
-    if damage > defense {
-        target.hp = 0.0_f64.max(target.hp - (damage - defense) * (1. - absorbtion));
-    }
+    target.hp = 0.0_f64.max(target.hp - target.mitigate(aspect, damage));
 }
@@ -2,12 +2,21 @@
 
 use serde::Deserialize;
 use serde::Serialize;
+use strum::IntoEnumIterator;
 
+use crate::accessory::Accessory;
+use crate::accessory::AccessoryIdentifier;
+use crate::affix::AffixKind;
+use crate::affix::RolledAffix;
 use crate::aspect::Aspect;
 use crate::attribute::Attribute;
 use crate::bodywear::Bodywear;
 use crate::bodywear::BodywearIdentifier;
 use crate::dot::DOT;
+use crate::effect::TargetingScheme;
+use crate::equipment_set::EquipmentSet;
+use crate::equipment_set::EquipmentSetIdentifier;
+use crate::equipment_set::SetBonus;
 use crate::footwear::Footwear;
 use crate::footwear::FootwearIdentifier;
 use crate::gender::Gender;
@@ -15,11 +24,21 @@ use crate::handwear::Handwear;
 use crate::handwear::HandwearIdentifier;
 use crate::headwear::Headwear;
 use crate::headwear::HeadwearIdentifier;
+use crate::item::Item;
+use crate::item::ItemInstance;
+use crate::lifetime::Lifetime;
 use crate::legwear::Legwear;
 use crate::legwear::LegwearIdentifier;
+use crate::modifier;
 use crate::modifier::Modifier;
 use crate::modifier::ModifierExpression;
+use crate::modifier::ModifierOrigin;
+use crate::modifier::ModifierTag;
+use crate::modifier::Stacking;
+use crate::offhand::Offhand;
+use crate::offhand::OffhandIdentifier;
 use crate::skill::SkillIdentifier;
+use crate::weapon::Weapon;
 use crate::weapon::WeaponIdentifier;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -36,12 +55,14 @@ pub struct Combatant {
     pub vigor: f64,
     pub vitality: f64,
 
-    pub bodywear: Option<BodywearIdentifier>,
-    pub footwear: Option<FootwearIdentifier>,
-    pub handwear: Option<HandwearIdentifier>,
-    pub headwear: Option<HeadwearIdentifier>,
-    pub legwear: Option<LegwearIdentifier>,
-    pub weapon: Option<WeaponIdentifier>,
+    pub bodywear: Option<ItemInstance<BodywearIdentifier>>,
+    pub footwear: Option<ItemInstance<FootwearIdentifier>>,
+    pub handwear: Option<ItemInstance<HandwearIdentifier>>,
+    pub headwear: Option<ItemInstance<HeadwearIdentifier>>,
+    pub legwear: Option<ItemInstance<LegwearIdentifier>>,
+    pub weapon: Option<ItemInstance<WeaponIdentifier>>,
+    pub offhand: Option<ItemInstance<OffhandIdentifier>>,
+    pub accessory: Option<ItemInstance<AccessoryIdentifier>>,
 
     pub hp: f64,
     pub fatigue: f64,
@@ -65,6 +86,14 @@ impl Combatant {
         vigor
     }
 
+    /// The share of max hp left, from 0 to 1, and 0 for the dead so a max hp of 0 never divides by zero
+    pub fn health(&self) -> f64 {
+        match self.alive() {
+            true => (self.hp / self.hp_max()).min(1.),
+            false => 0.,
+        }
+    }
+
     pub fn alive(&self) -> bool {
         self.hp > 0. && self.hp_max() > 0.
     }
@@ -90,9 +119,9 @@ impl Combatant {
         }
     }
 
-    pub fn attribute(&self, attribute: Attribute) -> f64 {
+    pub fn modifiers(&self, attribute: Attribute) -> &Vec<Modifier> {
         use Attribute::*;
-        let attribute_modifiers = match attribute {
+        match attribute {
             Agility => &self.agility_modifiers,
             Dexterity => &self.dexterity_modifiers,
             Intelligence => &self.intelligence_modifiers,
@@ -100,30 +129,118 @@ impl Combatant {
             Strength => &self.strength_modifiers,
             Vigor => &self.vigor_modifiers,
             Vitality => &self.vitality_modifiers,
-        };
-
-        let mut add = 0.;
-        let mut subtract = 0.;
-        let mut multiply = 1.;
-        for modifier in attribute_modifiers {
-            match modifier.expression {
-                ModifierExpression::Add(value) => match value.signum() {
-                    sign if sign == 1.0 => add += value,
-                    sign if sign == -1.0 => subtract += value,
-                    _ => (),
-                },
-                ModifierExpression::Multiply(value) => multiply *= value,
+        }
+    }
+
+    pub fn modifiers_mut(&mut self, attribute: Attribute) -> &mut Vec<Modifier> {
+        use Attribute::*;
+        match attribute {
+            Agility => &mut self.agility_modifiers,
+            Dexterity => &mut self.dexterity_modifiers,
+            Intelligence => &mut self.intelligence_modifiers,
+            Mind => &mut self.mind_modifiers,
+            Strength => &mut self.strength_modifiers,
+            Vigor => &mut self.vigor_modifiers,
+            Vitality => &mut self.vitality_modifiers,
+        }
+    }
+
+    /// Adds a modifier to an attribute, resolving it against modifiers of the same origin using its stacking policy.
+    /// Modifiers without a source never share an origin and always stack.
+    pub fn apply_modifier(&mut self, attribute: Attribute, modifier: Modifier) {
+        let origin = modifier.origin();
+        let same_origin = |existing: &Modifier| origin.is_some() && existing.origin() == origin;
+        let modifiers = self.modifiers_mut(attribute);
+
+        match modifier.stacking {
+            Stacking::Stack => modifiers.push(modifier),
+            Stacking::Refresh => {
+                let mut refreshed = false;
+                for existing in modifiers.iter_mut().filter(|existing| same_origin(existing)) {
+                    existing.lifetime = modifier.lifetime;
+                    existing.source = modifier.source;
+                    refreshed = true;
+                }
+
+                if !refreshed {
+                    modifiers.push(modifier);
+                }
+            },
+            Stacking::Replace => {
+                modifiers.retain(|existing| !same_origin(existing));
+                modifiers.push(modifier);
+            },
+            Stacking::MaxStacks(max_stacks) => {
+                if max_stacks == 0 {
+                    return;
+                }
+
+                while modifiers.iter().filter(|existing| same_origin(existing)).count() >= max_stacks as usize {
+                    let oldest = modifiers.iter().position(same_origin).unwrap();
+                    modifiers.remove(oldest);
+                }
+
+                modifiers.push(modifier);
+            },
+        }
+    }
+
+    pub fn remove_modifiers<F: Fn(&Modifier) -> bool>(&mut self, predicate: F) {
+        for attribute in Attribute::iter() {
+            self.modifiers_mut(attribute).retain(|modifier| !predicate(modifier));
+        }
+    }
+
+    pub fn remove_modifiers_from(&mut self, origin: ModifierOrigin) {
+        self.remove_modifiers(|modifier| modifier.origin() == Some(origin));
+    }
+
+    pub fn cleanse(&mut self, tag: ModifierTag) {
+        self.remove_modifiers(|modifier| modifier.tags.contains(&tag));
+    }
+
+    /// Modifiers on an attribute, including those granted by equipment
+    pub fn attribute_modifiers(&self, attribute: Attribute) -> Vec<Modifier> {
+        let mut modifiers = self.modifiers(attribute).clone();
+        if let Some(accessory) = &self.accessory {
+            for attribute_modifier in &<&Accessory>::from(accessory.base).modifiers {
+                if attribute_modifier.attribute == attribute {
+                    modifiers.push(attribute_modifier.modifier.clone());
+                }
+            }
+        }
+        for bonus in self.set_bonuses() {
+            for attribute_modifier in &bonus.modifiers {
+                if attribute_modifier.attribute == attribute {
+                    modifiers.push(attribute_modifier.modifier.clone());
+                }
+            }
+        }
+        for affix in self.affixes() {
+            if affix.kind() == AffixKind::Attribute(attribute) {
+                modifiers.push(Modifier {
+                    expression: ModifierExpression::Add(affix.value),
+                    lifetime: Lifetime::Constant,
+                    priority: None,
+                    stacking: Stacking::Stack,
+                    tags: vec![],
+                    source: None,
+                });
             }
         }
 
-        let mut value = self.attribute_raw(attribute);
-        value += add;
-        value -= value.min(subtract);
-        value *= multiply;
+        modifiers
+    }
 
-        value
+    /// The attribute after modifiers, see [`modifier::evaluate`] for the evaluation order. `PercentOf` modifiers
+    /// read the other attribute with its own `PercentOf` modifiers ignored, so they can't depend on each other
+    pub fn attribute(&self, attribute: Attribute) -> f64 {
+        modifier::evaluate(self.attribute_raw(attribute), &self.attribute_modifiers(attribute), |other| {
+            Some(modifier::evaluate(self.attribute_raw(other), &self.attribute_modifiers(other), |_| None))
+        })
     }
 
+    /// Damage before multipliers, only aspects for which [`Aspect::has_damage_formula`] holds are supported
     pub fn raw_damage(&self, aspect: Aspect) -> f64 {
         match aspect {
             Aspect::Fire => self.attribute(Attribute::Intelligence) * self.attribute(Attribute::Mind) * 0.5,
@@ -135,25 +252,301 @@ impl Combatant {
 
     pub fn defense(&self, aspect: Aspect) -> f64 {
         let mut value = 0.;
-        if let Some(identifier) = self.bodywear {
-            value += <&Bodywear>::from(identifier).defense(aspect);
+        if let Some(bodywear) = &self.bodywear {
+            value += <&Bodywear>::from(bodywear.base).defense(aspect);
+        }
+        if let Some(footwear) = &self.footwear {
+            value += <&Footwear>::from(footwear.base).defense(aspect);
+        }
+        if let Some(handwear) = &self.handwear {
+            value += <&Handwear>::from(handwear.base).defense(aspect);
         }
-        if let Some(identifier) = self.footwear {
-            value += <&Footwear>::from(identifier).defense(aspect);
+        if let Some(headwear) = &self.headwear {
+            value += <&Headwear>::from(headwear.base).defense(aspect);
         }
-        if let Some(identifier) = self.handwear {
-            value += <&Handwear>::from(identifier).defense(aspect);
+        if let Some(legwear) = &self.legwear {
+            value += <&Legwear>::from(legwear.base).defense(aspect);
         }
-        if let Some(identifier) = self.headwear {
-            value += <&Headwear>::from(identifier).defense(aspect);
+        if let Some(offhand) = &self.offhand {
+            value += <&Offhand>::from(offhand.base).defense(aspect);
         }
-        if let Some(identifier) = self.legwear {
-            value += <&Legwear>::from(identifier).defense(aspect);
+        for bonus in self.set_bonuses() {
+            value += bonus.defense(aspect);
+        }
+        for affix in self.affixes() {
+            if affix.kind() == AffixKind::Defense(aspect) {
+                value += affix.value;
+            }
         }
         value
     }
 
-    pub fn absorbtion(&self, _aspect: Aspect) -> f64 {
-        0. // TODO: determine where to get absorbtion values from
+    /// The probability of an incoming attack being blocked outright
+    pub fn block_chance(&self) -> f64 {
+        match &self.offhand {
+            Some(offhand) => <&Offhand>::from(offhand.base).block_chance().clamp(0., 1.),
+            None => 0.,
+        }
+    }
+
+    pub fn weapon_identifier(&self) -> Option<WeaponIdentifier> {
+        self.weapon.as_ref().map(|weapon| weapon.base)
+    }
+
+    /// The weapon held in the off hand when dual wielding
+    pub fn offhand_weapon(&self) -> Option<WeaponIdentifier> {
+        self.offhand.as_ref().and_then(|offhand| <&Offhand>::from(offhand.base).weapon())
+    }
+
+    /// Every equipped item along with its rarity and rolled affixes
+    pub fn equipped_items(&self) -> Vec<ItemInstance<Item>> {
+        fn instance<T: Copy>(slot: &Option<ItemInstance<T>>, item: fn(T) -> Item) -> Option<ItemInstance<Item>> {
+            slot.as_ref().map(|instance| ItemInstance {
+                base: item(instance.base),
+                rarity: instance.rarity,
+                affixes: instance.affixes.clone(),
+            })
+        }
+
+        [
+            instance(&self.bodywear, Item::Bodywear),
+            instance(&self.footwear, Item::Footwear),
+            instance(&self.handwear, Item::Handwear),
+            instance(&self.headwear, Item::Headwear),
+            instance(&self.legwear, Item::Legwear),
+            instance(&self.weapon, Item::Weapon),
+            instance(&self.offhand, Item::Offhand),
+            instance(&self.accessory, Item::Accessory),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    pub fn equipment(&self) -> Vec<Item> {
+        self.equipped_items().into_iter().map(|item| item.base).collect()
+    }
+
+    /// Affixes rolled on every equipped item
+    pub fn affixes(&self) -> Vec<RolledAffix> {
+        self.equipped_items().into_iter().flat_map(|item| item.affixes).collect()
+    }
+
+    /// Bonuses from every equipment set with enough members equipped
+    pub fn set_bonuses(&self) -> Vec<&'static SetBonus> {
+        EquipmentSetIdentifier::iter()
+            .flat_map(|identifier| <&EquipmentSet>::from(identifier).active_bonuses(self))
+            .collect()
+    }
+
+    /// How targets are chosen for a basic attack, unarmed attacks hit a single target
+    pub fn attack_targeting_scheme(&self) -> TargetingScheme {
+        match &self.weapon {
+            Some(weapon) => <&Weapon>::from(weapon.base).targeting_scheme(),
+            None => TargetingScheme::SingleTarget,
+        }
+    }
+
+    /// The hp a hit of the given damage takes off, nothing unless it gets past defense, after which absorbtion applies
+    pub fn mitigate(&self, aspect: Aspect, damage: f64) -> f64 {
+        let defense = self.defense(aspect);
+        match damage > defense {
+            true => (damage - defense) * (1. - self.absorbtion(aspect)),
+            false => 0.,
+        }
+    }
+
+    /// The fraction of damage beyond defense that is ignored
+    pub fn absorbtion(&self, aspect: Aspect) -> f64 {
+        let mut value = 0.;
+        for affix in self.affixes() {
+            if affix.kind() == AffixKind::Absorption(aspect) {
+                value += affix.value;
+            }
+        }
+        value.clamp(0., 1.)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Combatant;
+    use crate::aspect::Aspect;
+    use crate::attribute::Attribute;
+    use crate::bodywear::Bodywear;
+    use crate::footwear::Footwear;
+    use crate::gender::Gender;
+    use crate::handwear::Handwear;
+    use crate::item::ItemInstance;
+    use crate::legwear::Legwear;
+    use crate::lifetime::Lifetime;
+    use crate::modifier::Modifier;
+    use crate::modifier::ModifierExpression;
+    use crate::modifier::ModifierOrigin;
+    use crate::modifier::ModifierSource;
+    use crate::modifier::ModifierTag;
+    use crate::modifier::Stacking;
+    use crate::target::Target;
+
+    fn combatant() -> Combatant {
+        Combatant {
+            name: "Test".to_string(),
+            gender: Gender::None,
+            skills: vec![],
+            agility: 10.,
+            dexterity: 10.,
+            intelligence: 10.,
+            mind: 10.,
+            strength: 10.,
+            vigor: 10.,
+            vitality: 10.,
+            bodywear: None,
+            footwear: None,
+            handwear: None,
+            headwear: None,
+            legwear: None,
+            weapon: None,
+            offhand: None,
+            accessory: None,
+            hp: 10.,
+            fatigue: 0.,
+            dots: vec![],
+            agility_modifiers: vec![],
+            dexterity_modifiers: vec![],
+            intelligence_modifiers: vec![],
+            mind_modifiers: vec![],
+            strength_modifiers: vec![],
+            vigor_modifiers: vec![],
+            vitality_modifiers: vec![],
+        }
+    }
+
+    fn modifier(value: f64, duration: u32, stacking: Stacking, origin: &str) -> Modifier {
+        Modifier {
+            expression: ModifierExpression::Add(value),
+            lifetime: Lifetime::Active { duration },
+            priority: None,
+            stacking,
+            tags: vec![],
+            source: Some(ModifierSource {
+                origin: ModifierOrigin::Skill(origin.parse().unwrap()),
+                applier: Target { party_index: 0, member_index: 0 },
+            }),
+        }
+    }
+
+    fn tagged(value: f64, tags: Vec<ModifierTag>) -> Modifier {
+        Modifier { tags, source: None, ..modifier(value, 1, Stacking::Stack, "scorch") }
+    }
+
+    fn durations(combatant: &Combatant) -> Vec<Lifetime> {
+        combatant.modifiers(Attribute::Strength).iter().map(|modifier| modifier.lifetime).collect()
+    }
+
+    #[test]
+    fn health_without_max_hp_is_zero() {
+        let mut combatant = combatant();
+        combatant.hp = 5.;
+        assert_eq!(combatant.health(), 0.5);
+
+        combatant.apply_modifier(Attribute::Vigor, modifier(-10., 1, Stacking::Stack, "scorch"));
+        assert_eq!(combatant.hp_max(), 0.);
+        assert_eq!(combatant.health(), 0.);
+    }
+
+    #[test]
+    fn stack_adds_instances() {
+        let mut combatant = combatant();
+        combatant.apply_modifier(Attribute::Strength, modifier(1., 1, Stacking::Stack, "scorch"));
+        combatant.apply_modifier(Attribute::Strength, modifier(1., 2, Stacking::Stack, "scorch"));
+        assert_eq!(combatant.attribute(Attribute::Strength), 12.);
+    }
+
+    #[test]
+    fn unsourced_modifiers_always_stack() {
+        let mut combatant = combatant();
+        let unsourced = Modifier { source: None, ..modifier(1., 1, Stacking::Replace, "scorch") };
+        combatant.apply_modifier(Attribute::Strength, unsourced.clone());
+        combatant.apply_modifier(Attribute::Strength, unsourced);
+        assert_eq!(combatant.attribute(Attribute::Strength), 12.);
+    }
+
+    #[test]
+    fn refresh_resets_lifetime() {
+        let mut combatant = combatant();
+        combatant.apply_modifier(Attribute::Strength, modifier(1., 1, Stacking::Refresh, "scorch"));
+        combatant.apply_modifier(Attribute::Strength, modifier(1., 3, Stacking::Refresh, "scorch"));
+        assert_eq!(durations(&combatant), [Lifetime::Active { duration: 3 }]);
+        assert_eq!(combatant.attribute(Attribute::Strength), 11.);
+
+        // another origin doesn't refresh the first
+        combatant.apply_modifier(Attribute::Strength, modifier(1., 5, Stacking::Refresh, "sweep"));
+        assert_eq!(durations(&combatant), [Lifetime::Active { duration: 3 }, Lifetime::Active { duration: 5 }]);
+    }
+
+    #[test]
+    fn replace_removes_existing() {
+        let mut combatant = combatant();
+        combatant.apply_modifier(Attribute::Strength, modifier(1., 1, Stacking::Stack, "scorch"));
+        combatant.apply_modifier(Attribute::Strength, modifier(1., 1, Stacking::Stack, "scorch"));
+        combatant.apply_modifier(Attribute::Strength, modifier(5., 2, Stacking::Replace, "scorch"));
+        assert_eq!(durations(&combatant), [Lifetime::Active { duration: 2 }]);
+        assert_eq!(combatant.attribute(Attribute::Strength), 15.);
+    }
+
+    #[test]
+    fn max_stacks_replaces_oldest() {
+        let mut combatant = combatant();
+        for duration in 1..=4 {
+            combatant.apply_modifier(Attribute::Strength, modifier(1., duration, Stacking::MaxStacks(3), "scorch"));
+        }
+        assert_eq!(durations(&combatant), [2, 3, 4].map(|duration| Lifetime::Active { duration }));
+        assert_eq!(combatant.attribute(Attribute::Strength), 13.);
+
+        combatant.apply_modifier(Attribute::Strength, modifier(1., 1, Stacking::MaxStacks(0), "scorch"));
+        assert_eq!(combatant.modifiers(Attribute::Strength).len(), 3);
+    }
+
+    #[test]
+    fn cleanse_removes_tagged_modifiers() {
+        let mut combatant = combatant();
+        combatant.apply_modifier(Attribute::Strength, tagged(1., vec![ModifierTag::Buff]));
+        combatant.apply_modifier(Attribute::Strength, tagged(-2., vec![ModifierTag::Debuff, ModifierTag::Magical]));
+        combatant.apply_modifier(Attribute::Agility, tagged(-3., vec![ModifierTag::Debuff]));
+
+        combatant.cleanse(ModifierTag::Debuff);
+        assert_eq!(combatant.attribute(Attribute::Strength), 11.);
+        assert_eq!(combatant.attribute(Attribute::Agility), 10.);
+
+        combatant.cleanse(ModifierTag::Physical);
+        assert_eq!(combatant.attribute(Attribute::Strength), 11.);
+    }
+
+    #[test]
+    fn set_bonuses_by_pieces_equipped() {
+        let bodywear = "breakers_longsleeve".parse().unwrap();
+        let footwear = "breakers_sneakers".parse().unwrap();
+        let handwear = "breakers_wraps".parse().unwrap();
+        let legwear = "breakers_harem_pants".parse().unwrap();
+
+        let mut combatant = combatant();
+        combatant.bodywear = Some(ItemInstance::new(bodywear));
+        assert!(combatant.set_bonuses().is_empty());
+
+        combatant.footwear = Some(ItemInstance::new(footwear));
+        let pieces_defense = <&Bodywear>::from(bodywear).defense(Aspect::Physical)
+            + <&Footwear>::from(footwear).defense(Aspect::Physical);
+        assert_eq!(combatant.set_bonuses().len(), 1);
+        assert_eq!(combatant.defense(Aspect::Physical), pieces_defense + 1.);
+        assert_eq!(combatant.attribute(Attribute::Agility), 10.);
+
+        combatant.handwear = Some(ItemInstance::new(handwear));
+        combatant.legwear = Some(ItemInstance::new(legwear));
+        let pieces_defense = pieces_defense
+            + <&Handwear>::from(handwear).defense(Aspect::Physical)
+            + <&Legwear>::from(legwear).defense(Aspect::Physical);
+        assert_eq!(combatant.set_bonuses().len(), 2);
+        assert_eq!(combatant.defense(Aspect::Physical), pieces_defense + 1.);
+        assert_eq!(combatant.attribute(Attribute::Agility), 12.);
     }
 }
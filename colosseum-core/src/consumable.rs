@@ -1,14 +1,15 @@
 // Copyright 2021 Chay Nabors.
 
-#[path = "generated/consumable.rs"]
-mod consumable;
-pub use consumable::ConsumableIdentifier;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::content::Identifier;
 use crate::effect::Effect;
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+pub type ConsumableIdentifier = Identifier<Consumable>;
+
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
 pub struct Consumable {
     pub display_name: String,
     pub description: String,
@@ -0,0 +1,789 @@
+// Copyright 2021 Chay Nabors.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use include_dir::include_dir;
+use include_dir::Dir;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::InstanceType;
+use schemars::schema::Metadata;
+use schemars::schema::RootSchema;
+use schemars::schema::Schema;
+use schemars::schema::SchemaObject;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::de::Error;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use crate::accessory::Accessory;
+use crate::affix::Affix;
+use crate::archetype::Archetype;
+use crate::bodywear::Bodywear;
+use crate::combat_state::FNV_OFFSET_BASIS;
+use crate::combat_state::FNV_PRIME;
+use crate::consumable::Consumable;
+use crate::encounter::Encounter;
+use crate::equipment_set::EquipmentSet;
+use crate::footwear::Footwear;
+use crate::handwear::Handwear;
+use crate::headwear::Headwear;
+use crate::legwear::Legwear;
+use crate::locale::StringTable;
+use crate::loot_table::LootTable;
+use crate::offhand::Offhand;
+use crate::roster::Roster;
+use crate::skill::Skill;
+use crate::weapon::Weapon;
+
+const EMBEDDED: Dir = include_dir!("$CARGO_MANIFEST_DIR/content");
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: RwLock<&'static Registry> =
+        RwLock::new(Box::leak(Box::new(Registry::embedded().expect("embedded content is invalid"))));
+    static ref KEYS: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
+
+thread_local! {
+    /// The keys of every category while a registry is loaded on this thread, references are resolved against these
+    static LOADING: RefCell<Option<HashMap<&'static str, HashSet<&'static str>>>> = const { RefCell::new(None) };
+}
+
+/// The registry content is read from, the embedded content unless another has been installed
+pub fn registry() -> &'static Registry {
+    *REGISTRY.read().unwrap()
+}
+
+/// Makes a registry the one content is read from
+///
+/// Replaced registries are leaked rather than dropped, content borrowed from them stays valid for the life of the
+/// program
+pub fn install(registry: Registry) -> &'static Registry {
+    let registry = Box::leak(Box::new(registry));
+    *REGISTRY.write().unwrap() = registry;
+    registry
+}
+
+fn intern(key: &str) -> &'static str {
+    let mut keys = KEYS.lock().unwrap();
+    match keys.get(key) {
+        Some(key) => key,
+        None => {
+            let key = Box::leak(key.to_owned().into_boxed_str());
+            keys.insert(key);
+            key
+        },
+    }
+}
+
+/// A kind of content, each kind is loaded from its own directory of the content tree
+pub trait Content: DeserializeOwned + JsonSchema + Send + Sync + 'static {
+    /// The directory the kind is loaded from, also used to name it in errors
+    const CATEGORY: &'static str;
+
+    fn store(registry: &Registry) -> &Store<Self>;
+}
+
+/// Names an entry of a kind of content by the stem of the file it was loaded from
+///
+/// Identifiers are interned keys, so they stay meaningful across registries and are written out as their key
+pub struct Identifier<T> {
+    key: &'static str,
+    content: PhantomData<fn() -> T>,
+}
+
+impl<T> Identifier<T> {
+    fn interned(key: &'static str) -> Self {
+        Self { key, content: PhantomData }
+    }
+
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+}
+
+impl<T: Content> Identifier<T> {
+    /// Every entry of the kind in the installed registry, in key order
+    pub fn iter() -> impl Iterator<Item = Self> {
+        T::store(registry()).identifiers()
+    }
+
+    /// Resolves a key against the registry being loaded on this thread, or the installed registry otherwise
+    fn resolve(key: &str) -> Result<Self, UnknownIdentifier> {
+        let loading = LOADING.with(|loading| {
+            loading.borrow().as_ref().map(|keys| keys.get(T::CATEGORY).is_some_and(|keys| keys.contains(key)))
+        });
+
+        match loading.unwrap_or_else(|| T::store(registry()).contains(key)) {
+            true => Ok(Self::interned(intern(key))),
+            false => Err(UnknownIdentifier { category: T::CATEGORY, key: key.into() }),
+        }
+    }
+}
+
+impl<T> Clone for Identifier<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Identifier<T> {}
+
+impl<T> PartialEq for Identifier<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<T> Eq for Identifier<T> {}
+
+impl<T> Hash for Identifier<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state)
+    }
+}
+
+impl<T> std::fmt::Debug for Identifier<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.key)
+    }
+}
+
+impl<T> Display for Identifier<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key)
+    }
+}
+
+impl<T: Content> FromStr for Identifier<T> {
+    type Err = UnknownIdentifier;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::resolve(s)
+    }
+}
+
+impl<T> Serialize for Identifier<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.key)
+    }
+}
+
+impl<'de, T: Content> Deserialize<'de> for Identifier<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key = String::deserialize(deserializer)?;
+        Self::resolve(&key).map_err(D::Error::custom)
+    }
+}
+
+/// A string naming one of the entries of the installed registry, so schemas only accept keys that are loaded
+impl<T: Content> JsonSchema for Identifier<T> {
+    fn schema_name() -> String {
+        let content = std::any::type_name::<T>().rsplit("::").next().unwrap_or(T::CATEGORY);
+        format!("{}Identifier", content)
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let description = format!("The key of a {}, the stem of the file it is loaded from", T::CATEGORY);
+        SchemaObject {
+            metadata: Some(Box::new(Metadata { description: Some(description), ..Default::default() })),
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(Self::iter().map(|identifier| identifier.key.into()).collect()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Every entry of a kind of content, keyed and ordered by identifier
+#[derive(Debug)]
+pub struct Store<T> {
+    entries: BTreeMap<&'static str, T>,
+}
+
+impl<T> Default for Store<T> {
+    fn default() -> Self {
+        Self { entries: BTreeMap::new() }
+    }
+}
+
+impl<T: Content> Store<T> {
+    pub fn get(&self, identifier: Identifier<T>) -> Option<&T> {
+        self.entries.get(identifier.key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub fn identifiers(&self) -> impl Iterator<Item = Identifier<T>> + '_ {
+        self.entries.keys().map(|key| Identifier::interned(key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+macro_rules! registry {
+    ($($field:ident: $content:ty = $category:literal,)*) => {
+        /// Every piece of content the game is played with, loaded at runtime rather than compiled in
+        #[derive(Debug, Default)]
+        pub struct Registry {
+            $(pub $field: Store<$content>,)*
+            files: Vec<ContentFile>,
+            version: String,
+        }
+
+        impl Registry {
+            /// The category and key of every entry
+            pub fn keys(&self) -> Vec<(&'static str, &'static str)> {
+                let mut keys = vec![];
+                $(keys.extend(self.$field.entries.keys().map(|key| ($category, *key)));)*
+                keys
+            }
+
+            fn insert(&mut self, file: &ContentFile) -> Result<(), ContentError> {
+                match file.category.as_str() {
+                    $($category => {
+                        self.$field.entries.insert(intern(&file.key), file.parse()?);
+                    },)*
+                    _ => return Err(ContentError::UnknownCategory(file.path.clone())),
+                }
+
+                Ok(())
+            }
+        }
+
+        /// A JSON Schema for the files of every category, identifiers limited to the entries of the installed registry
+        pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+            vec![$(($category, SchemaGenerator::default().into_root_schema_for::<$content>()),)*]
+        }
+
+        $(
+            impl Content for $content {
+                const CATEGORY: &'static str = $category;
+
+                fn store(registry: &Registry) -> &Store<Self> {
+                    &registry.$field
+                }
+            }
+
+            impl From<Identifier<$content>> for &'static $content {
+                fn from(from: Identifier<$content>) -> Self {
+                    registry().$field.get(from).unwrap_or_else(|| panic!("{} '{}' is not loaded", $category, from))
+                }
+            }
+        )*
+    };
+}
+
+registry! {
+    accessories: Accessory = "accessory",
+    affixes: Affix = "affix",
+    bodywear: Bodywear = "bodywear",
+    combatants: Archetype = "combatant",
+    consumables: Consumable = "consumable",
+    encounters: Encounter = "encounter",
+    equipment_sets: EquipmentSet = "equipment_set",
+    footwear: Footwear = "footwear",
+    handwear: Handwear = "handwear",
+    headwear: Headwear = "headwear",
+    legwear: Legwear = "legwear",
+    locales: StringTable = "locale",
+    loot_tables: LootTable = "loot_table",
+    offhands: Offhand = "offhand",
+    rosters: Roster = "roster",
+    skills: Skill = "skill",
+    weapons: Weapon = "weapon",
+}
+
+impl Registry {
+    /// The content embedded in this build
+    pub fn embedded() -> Result<Self, ContentError> {
+        let (registry, errors) = Self::from_files(embedded_files());
+        first_error(registry, errors)
+    }
+
+    /// The embedded content overlaid by each directory in turn, so mods can add entries or replace existing ones
+    ///
+    /// Directories are laid out like the embedded content, a subdirectory per category holding a file per entry
+    pub fn load<P: AsRef<Path>>(directories: &[P]) -> Result<Self, ContentError> {
+        let (registry, errors) = Self::load_lenient(directories);
+        first_error(registry, errors)
+    }
+
+    /// Loads like [`Registry::load`] but carries on past files that fail, leaving them and the entries referring to them
+    /// out and returning every error
+    pub fn load_lenient<P: AsRef<Path>>(directories: &[P]) -> (Self, Vec<ContentError>) {
+        let mut files = embedded_files();
+        let mut errors = vec![];
+        for directory in directories {
+            read_directory(directory.as_ref(), &mut files, &mut errors);
+        }
+
+        let (registry, load_errors) = Self::from_files(files);
+        errors.extend(load_errors);
+        (registry, errors)
+    }
+
+    /// Identifies the loaded content, registries loaded from identical content share a version
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// The file every entry was loaded from, ordered by category and key
+    pub fn files(&self) -> &[ContentFile] {
+        &self.files
+    }
+
+    /// The key and a hash of the file of every entry, for telling whether another build plays with the same content
+    pub fn manifest(&self) -> ContentManifest {
+        let entries = self
+            .files
+            .iter()
+            .map(|file| ManifestEntry {
+                category: file.category.clone(),
+                key: file.key.clone(),
+                hash: file
+                    .contents
+                    .bytes()
+                    .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME)),
+            })
+            .collect();
+
+        ContentManifest { version: self.version.clone(), entries }
+    }
+
+    /// Exactly the given files, as taken from another registry, rather than files laid over the embedded content
+    pub fn from_pushed(files: Vec<ContentFile>) -> Result<Self, ContentError> {
+        let (registry, errors) = Self::from_files(files);
+        first_error(registry, errors)
+    }
+
+    fn from_files(files: Vec<ContentFile>) -> (Self, Vec<ContentError>) {
+        let mut layered = BTreeMap::new();
+        for file in files {
+            layered.insert((file.category.clone(), file.key.clone()), file);
+        }
+
+        let mut hash = FNV_OFFSET_BASIS;
+        for file in layered.values() {
+            let name = format!("{}/{}", file.category, file.key);
+            for byte in name.bytes().chain(file.contents.bytes()) {
+                hash = (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+            }
+        }
+
+        // an entry referring to one that failed to load fails in turn, so loaded entries only refer to loaded entries
+        let mut errors = vec![];
+        let mut registry = loop {
+            let mut keys: HashMap<&'static str, HashSet<&'static str>> = HashMap::new();
+            for (category, key) in layered.keys() {
+                keys.entry(intern(category)).or_default().insert(intern(key));
+            }
+
+            let mut registry = Registry::default();
+            let mut failed = vec![];
+            LOADING.with(|loading| *loading.borrow_mut() = Some(keys));
+            for (name, file) in &layered {
+                if let Err(e) = registry.insert(file) {
+                    errors.push(e);
+                    failed.push(name.clone());
+                }
+            }
+            LOADING.with(|loading| *loading.borrow_mut() = None);
+
+            if failed.is_empty() {
+                break registry;
+            }
+            for name in failed {
+                layered.remove(&name);
+            }
+        };
+
+        registry.files = layered.into_values().collect();
+        registry.version = format!("{:016x}", hash);
+        (registry, errors)
+    }
+}
+
+fn first_error(registry: Registry, errors: Vec<ContentError>) -> Result<Registry, ContentError> {
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(registry),
+    }
+}
+
+/// Reloads content whenever the files in its directories change, so tweaks show up without a restart
+///
+/// The directories are polled rather than subscribed to, and callers decide when a reload is safe, typically between
+/// matches
+pub struct ContentWatcher {
+    directories: Vec<PathBuf>,
+    interval: Duration,
+    polled: Instant,
+    snapshot: Vec<(PathBuf, Option<SystemTime>, u64)>,
+}
+
+impl ContentWatcher {
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(directories: Vec<PathBuf>, interval: Duration) -> Self {
+        let snapshot = snapshot(&directories);
+        Self { directories, interval, polled: Instant::now(), snapshot }
+    }
+
+    /// Checks for changes at most once per interval, loading and installing the content again if any file was added,
+    /// removed or modified
+    ///
+    /// Returns none when nothing changed. Content that fails to load, or that drops entries which may still be in
+    /// use, is returned as an error and the installed content is left as it was until the next change
+    pub fn poll(&mut self) -> Option<Result<&'static Registry, ContentError>> {
+        if self.polled.elapsed() < self.interval {
+            return None;
+        }
+        self.polled = Instant::now();
+
+        let snapshot = snapshot(&self.directories);
+        if snapshot == self.snapshot {
+            return None;
+        }
+        self.snapshot = snapshot;
+
+        Some(Registry::load(&self.directories).and_then(|loaded| {
+            let keys = loaded.keys();
+            match registry().keys().into_iter().find(|key| !keys.contains(key)) {
+                Some((category, key)) => Err(ContentError::Removed { category, key }),
+                None => Ok(install(loaded)),
+            }
+        }))
+    }
+}
+
+/// Every content file under the directories with its modification time and length, in a consistent order
+fn snapshot(directories: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+    let mut snapshot = vec![];
+    for directory in directories {
+        for category in entries(directory, &mut vec![]).into_iter().filter(|path| path.is_dir()) {
+            for path in entries(&category, &mut vec![]) {
+                if let Ok(metadata) = fs::metadata(&path) {
+                    snapshot.push((path, metadata.modified().ok(), metadata.len()));
+                }
+            }
+        }
+    }
+
+    snapshot
+}
+
+/// The formats content files are written in, told apart by extension
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ContentFormat {
+    Json,
+    Ron,
+    Toml,
+}
+
+impl ContentFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(ContentFormat::Json),
+            "ron" => Some(ContentFormat::Ron),
+            "toml" => Some(ContentFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// A content file, either read from disk or embedded in the build
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContentFile {
+    pub category: String,
+    pub key: String,
+    pub path: PathBuf,
+    pub format: ContentFormat,
+    pub contents: String,
+}
+
+impl ContentFile {
+    /// Content files are `<category>/<key>.<json|ron|toml>`, anything else is not content
+    fn new(path: &Path, contents: String) -> Option<Self> {
+        Some(Self {
+            category: path.parent()?.file_name()?.to_str()?.to_owned(),
+            key: path.file_stem()?.to_str()?.to_owned(),
+            path: path.to_owned(),
+            format: ContentFormat::from_path(path)?,
+            contents,
+        })
+    }
+
+    fn parse<T: DeserializeOwned>(&self) -> Result<T, ContentError> {
+        let parsed = match self.format {
+            ContentFormat::Json => serde_json::from_str(&self.contents).map_err(ParseError::Json),
+            ContentFormat::Ron => ron::Options::default()
+                .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+                .from_str(&self.contents)
+                .map_err(ParseError::Ron),
+            ContentFormat::Toml => toml::from_str(&self.contents).map_err(ParseError::Toml),
+        };
+
+        parsed.map_err(|error| ContentError::Parse { path: self.path.clone(), error })
+    }
+}
+
+fn embedded_files() -> Vec<ContentFile> {
+    EMBEDDED
+        .dirs()
+        .flat_map(|directory| directory.files())
+        .filter_map(|file| {
+            let path = Path::new("content").join(file.path());
+            ContentFile::new(&path, String::from_utf8_lossy(file.contents()).into_owned())
+        })
+        .collect()
+}
+
+fn read_directory(directory: &Path, files: &mut Vec<ContentFile>, errors: &mut Vec<ContentError>) {
+    for category in entries(directory, errors).into_iter().filter(|path| path.is_dir()) {
+        let mut keys: HashMap<String, PathBuf> = HashMap::new();
+        for path in entries(&category, errors) {
+            if ContentFormat::from_path(&path).is_none() {
+                continue;
+            }
+
+            match fs::read_to_string(&path) {
+                Ok(contents) => {
+                    if let Some(file) = ContentFile::new(&path, contents) {
+                        match keys.get(&file.key) {
+                            Some(other) => errors.push(ContentError::Duplicate { path, other: other.clone() }),
+                            None => {
+                                keys.insert(file.key.clone(), path);
+                                files.push(file);
+                            },
+                        }
+                    }
+                },
+                Err(e) => errors.push(io(&path)(e)),
+            }
+        }
+    }
+}
+
+/// The path of everything in a directory, sorted so loading never depends on the order the filesystem lists them in
+fn entries(directory: &Path, errors: &mut Vec<ContentError>) -> Vec<PathBuf> {
+    let mut paths = vec![];
+    match fs::read_dir(directory) {
+        Ok(read_dir) => {
+            for entry in read_dir {
+                match entry {
+                    Ok(entry) => paths.push(entry.path()),
+                    Err(e) => errors.push(io(directory)(e)),
+                }
+            }
+        },
+        Err(e) => errors.push(io(directory)(e)),
+    }
+
+    paths.sort();
+    paths
+}
+
+fn io(path: &Path) -> impl FnOnce(std::io::Error) -> ContentError + '_ {
+    move |error| ContentError::Io { path: path.to_owned(), error }
+}
+
+#[derive(Debug)]
+pub enum ContentError {
+    /// Two files of a directory share a category and key, differing only in format
+    Duplicate {
+        path: PathBuf,
+        other: PathBuf,
+    },
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        error: ParseError,
+    },
+    Removed {
+        category: &'static str,
+        key: &'static str,
+    },
+    UnknownCategory(PathBuf),
+}
+
+impl Display for ContentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContentError::Duplicate { path, other } => {
+                write!(f, "{}: defines the same entry as {}", path.display(), other.display())
+            },
+            ContentError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ContentError::Parse { path, error } => write!(f, "{}: {}", path.display(), error),
+            ContentError::Removed { category, key } => {
+                write!(f, "{} '{}' was removed while it may be in use", category, key)
+            },
+            ContentError::UnknownCategory(path) => write!(f, "{}: not in a known content category", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Json(serde_json::Error),
+    Ron(ron::error::SpannedError),
+    Toml(toml::de::Error),
+}
+
+impl ParseError {
+    /// The line the error was found on, counting from 1
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParseError::Json(error) => Some(error.line()),
+            ParseError::Ron(error) => Some(error.position.line),
+            ParseError::Toml(error) => error.line_col().map(|(line, _)| line + 1),
+        }
+    }
+
+    /// The error without the location each format writes out in its own way
+    pub fn message(&self) -> String {
+        match self {
+            ParseError::Json(error) => {
+                let location = format!(" at line {} column {}", error.line(), error.column());
+                error.to_string().trim_end_matches(&location).to_owned()
+            },
+            ParseError::Ron(error) => error.code.to_string(),
+            ParseError::Toml(error) => match error.line_col() {
+                Some((line, column)) => {
+                    let location = format!(" at line {} column {}", line + 1, column + 1);
+                    error.to_string().trim_end_matches(&location).to_owned()
+                },
+                None => error.to_string(),
+            },
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Json(error) => write!(f, "{}", error),
+            ParseError::Ron(error) => write!(f, "{}", error),
+            ParseError::Toml(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Every entry of a registry and a hash of its file, exchanged so client and server can check their content agrees
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ContentManifest {
+    pub version: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    pub category: String,
+    pub key: String,
+    pub hash: u64,
+}
+
+impl ContentManifest {
+    /// Every entry that isn't the same in both manifests, ordered by category and key
+    pub fn differences(&self, theirs: &ContentManifest) -> Vec<ContentDifference> {
+        let index = |manifest: &ContentManifest| -> BTreeMap<(String, String), u64> {
+            manifest.entries.iter().map(|entry| ((entry.category.clone(), entry.key.clone()), entry.hash)).collect()
+        };
+        let ours = index(self);
+        let mut theirs = index(theirs);
+
+        let mut differences = vec![];
+        for ((category, key), hash) in ours {
+            match theirs.remove(&(category.clone(), key.clone())) {
+                Some(their_hash) if their_hash == hash => (),
+                Some(_) => differences.push(ContentDifference::Changed { category, key }),
+                None => differences.push(ContentDifference::OnlyOurs { category, key }),
+            }
+        }
+        differences.extend(theirs.into_keys().map(|(category, key)| ContentDifference::OnlyTheirs { category, key }));
+        differences.sort_by(|a, b| a.entry().cmp(&b.entry()));
+
+        differences
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ContentDifference {
+    /// Both sides have the entry but its files differ
+    Changed { category: String, key: String },
+    /// Only the side comparing has the entry
+    OnlyOurs { category: String, key: String },
+    /// Only the side compared against has the entry
+    OnlyTheirs { category: String, key: String },
+}
+
+impl ContentDifference {
+    fn entry(&self) -> (&str, &str) {
+        match self {
+            ContentDifference::Changed { category, key }
+            | ContentDifference::OnlyOurs { category, key }
+            | ContentDifference::OnlyTheirs { category, key } => (category, key),
+        }
+    }
+}
+
+impl Display for ContentDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContentDifference::Changed { category, key } => write!(f, "{} '{}' differs", category, key),
+            ContentDifference::OnlyOurs { category, key } => {
+                write!(f, "{} '{}' is missing on the other side", category, key)
+            },
+            ContentDifference::OnlyTheirs { category, key } => {
+                write!(f, "{} '{}' is only on the other side", category, key)
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownIdentifier {
+    pub category: &'static str,
+    pub key: String,
+}
+
+impl Display for UnknownIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown {} '{}'", self.category, self.key)
+    }
+}
+
+impl std::error::Error for UnknownIdentifier {}
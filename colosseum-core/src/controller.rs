@@ -0,0 +1,325 @@
+// Copyright 2021 Chay Nabors.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat_event::CombatEvent;
+use crate::combat_state::CombatState;
+use crate::combatant::Combatant;
+use crate::consumable::Consumable;
+use crate::effect::EffectSource;
+use crate::effect::TargetFlag;
+use crate::effect::TargetingScheme;
+use crate::item::Item;
+use crate::mcts::MctsBudget;
+use crate::mcts::MctsController;
+use crate::skill::Skill;
+use crate::target::Target;
+use crate::weapon::Weapon;
+
+/// Decides the event a combatant performs on its turn
+pub trait Controller {
+    fn choose(&mut self, state: &CombatState, actor: Target) -> CombatEvent;
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub enum ControllerKind {
+    Random,
+    #[default]
+    Greedy,
+    Lookahead,
+    Mcts,
+}
+
+impl ControllerKind {
+    pub fn build(&self, seed: u64) -> Box<dyn Controller> {
+        match self {
+            ControllerKind::Random => Box::new(RandomController::new(seed)),
+            ControllerKind::Greedy => Box::new(GreedyController::new(seed)),
+            ControllerKind::Lookahead => Box::new(LookaheadController::new(seed, LookaheadController::DEFAULT_DEPTH)),
+            ControllerKind::Mcts => Box::new(MctsController::new(seed, MctsBudget::default())),
+        }
+    }
+}
+
+impl Display for ControllerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControllerKind::Random => write!(f, "random"),
+            ControllerKind::Greedy => write!(f, "greedy"),
+            ControllerKind::Lookahead => write!(f, "lookahead"),
+            ControllerKind::Mcts => write!(f, "mcts"),
+        }
+    }
+}
+
+impl FromStr for ControllerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(ControllerKind::Random),
+            "greedy" => Ok(ControllerKind::Greedy),
+            "lookahead" => Ok(ControllerKind::Lookahead),
+            "mcts" => Ok(ControllerKind::Mcts),
+            _ => Err(format!("unknown controller '{}', expected 'random', 'greedy', 'lookahead' or 'mcts'", s)),
+        }
+    }
+}
+
+/// Every event the actor may perform this turn, skipping is always among them
+pub fn valid_events(state: &CombatState, actor: Target) -> Vec<CombatEvent> {
+    let active = combatant(state, actor);
+    let mut events = vec![];
+
+    let (attack_flags, attack_scheme) = match active.weapon_identifier() {
+        Some(weapon) => {
+            let weapon = <&Weapon>::from(weapon);
+            (&weapon.effect.target_flags[..], weapon.targeting_scheme())
+        },
+        None => (&[][..], TargetingScheme::SingleTarget),
+    };
+    for targets in target_combinations(valid_targets(state, actor, attack_flags), attack_scheme) {
+        events.push(CombatEvent::AttackEvent { source: actor, targets });
+    }
+
+    for skill in &active.skills {
+        let effect = &<&Skill>::from(*skill).effect;
+        let targets = valid_targets(state, actor, &effect.target_flags);
+        for targets in target_combinations(targets, effect.targeting_scheme()) {
+            events.push(CombatEvent::SkillEvent { source: actor, skill: *skill, targets });
+        }
+    }
+
+    let mut consumables = vec![];
+    for item in &state.parties[actor.party_index].inventory {
+        if let Item::Consumable(consumable) = item.base {
+            if !consumables.contains(&consumable) {
+                consumables.push(consumable);
+            }
+        }
+    }
+    for consumable in consumables {
+        let effect = &<&Consumable>::from(consumable).effect;
+        let targets = valid_targets(state, actor, &effect.target_flags);
+        for targets in target_combinations(targets, effect.targeting_scheme()) {
+            events.push(CombatEvent::ConsumableEvent { source: actor, consumable, targets });
+        }
+    }
+
+    events.push(CombatEvent::SkipEvent);
+    events
+}
+
+/// Whether the actor may perform the event this turn, with its targets named in any order
+pub fn is_valid_event(state: &CombatState, actor: Target, event: &CombatEvent) -> bool {
+    let mut event = event.clone();
+    match &mut event {
+        CombatEvent::AttackEvent { targets, .. }
+        | CombatEvent::ConsumableEvent { targets, .. }
+        | CombatEvent::SkillEvent { targets, .. } => {
+            targets.sort_by_key(|target| (target.party_index, target.member_index))
+        },
+        CombatEvent::SkipEvent => (),
+    }
+
+    valid_events(state, actor).contains(&event)
+}
+
+/// Living combatants satisfying every flag of at least one of the flag groups, no groups allows anyone
+pub fn valid_targets(state: &CombatState, actor: Target, target_flags: &[Vec<TargetFlag>]) -> Vec<Target> {
+    state
+        .get_target_list()
+        .into_iter()
+        .filter(|target| {
+            let candidate = combatant(state, *target);
+            let source = match *target == actor {
+                true => EffectSource::Origin,
+                false => EffectSource::Other(combatant(state, actor)),
+            };
+
+            candidate.alive()
+                && (target_flags.is_empty()
+                    || target_flags.iter().any(|group| group.iter().all(|flag| flag.satisfied(candidate, source))))
+        })
+        .collect()
+}
+
+fn target_combinations(targets: Vec<Target>, targeting_scheme: TargetingScheme) -> Vec<Vec<Target>> {
+    if targets.is_empty() {
+        return vec![];
+    }
+
+    match targeting_scheme {
+        TargetingScheme::All => vec![targets],
+        TargetingScheme::SingleTarget => targets.into_iter().map(|target| vec![target]).collect(),
+        TargetingScheme::MultiTarget(count) => {
+            let mut combinations = vec![];
+            combine(&targets, count.min(targets.len()), &mut vec![], &mut combinations);
+            combinations
+        },
+    }
+}
+
+fn combine(targets: &[Target], count: usize, chosen: &mut Vec<Target>, combinations: &mut Vec<Vec<Target>>) {
+    if chosen.len() == count {
+        combinations.push(chosen.clone());
+        return;
+    }
+
+    for i in 0..targets.len() {
+        chosen.push(targets[i]);
+        combine(&targets[i + 1..], count, chosen, combinations);
+        chosen.pop();
+    }
+}
+
+fn combatant(state: &CombatState, target: Target) -> &Combatant {
+    &state.parties[target.party_index].members[target.member_index]
+}
+
+/// How favourable the state is for a party, the share of hp left on its side less the share left on the others'
+pub fn evaluate(state: &CombatState, party_index: usize) -> f64 {
+    if state.is_over() {
+        return match state.victor() {
+            Some(victor) if victor == party_index => 100.,
+            _ => -100.,
+        };
+    }
+
+    let mut score = 0.;
+    for (index, party) in state.parties.iter().enumerate() {
+        let share = party.members.iter().map(Combatant::health).sum::<f64>();
+        match index == party_index {
+            true => score += share,
+            false => score -= share,
+        }
+    }
+
+    score
+}
+
+/// Copies the state with its rng reseeded, so chance plays out without peeking at the real outcome
+pub(crate) fn determinize<R: Rng>(state: &CombatState, rng: &mut R) -> CombatState {
+    let mut state = state.clone();
+    state.rng = Pcg32::seed_from_u64(rng.gen());
+    state
+}
+
+/// Performs any valid event other than skipping, chosen uniformly
+pub struct RandomController {
+    rng: Pcg32,
+}
+
+impl RandomController {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: Pcg32::seed_from_u64(seed) }
+    }
+}
+
+impl Controller for RandomController {
+    fn choose(&mut self, state: &CombatState, actor: Target) -> CombatEvent {
+        let mut events = valid_events(state, actor);
+        let skip = events.pop().unwrap();
+
+        events.choose(&mut self.rng).cloned().unwrap_or(skip)
+    }
+}
+
+/// Performs the event that most improves its party's standing this turn, ties broken at random
+pub struct GreedyController {
+    rng: Pcg32,
+}
+
+impl GreedyController {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: Pcg32::seed_from_u64(seed) }
+    }
+}
+
+impl Controller for GreedyController {
+    fn choose(&mut self, state: &CombatState, actor: Target) -> CombatEvent {
+        let mut best = vec![];
+        let mut best_score = f64::MIN;
+
+        for event in valid_events(state, actor) {
+            let mut outcome = determinize(state, &mut self.rng);
+            outcome.apply_event(&event);
+
+            let score = evaluate(&outcome, actor.party_index);
+            if score > best_score {
+                best_score = score;
+                best.clear();
+            }
+            if score == best_score {
+                best.push(event);
+            }
+        }
+
+        best.choose(&mut self.rng).cloned().unwrap_or(CombatEvent::SkipEvent)
+    }
+}
+
+/// Searches a fixed number of turns ahead, assuming every other party plays against it
+pub struct LookaheadController {
+    rng: Pcg32,
+    depth: u32,
+}
+
+impl LookaheadController {
+    pub const DEFAULT_DEPTH: u32 = 2;
+
+    pub fn new(seed: u64, depth: u32) -> Self {
+        Self { rng: Pcg32::seed_from_u64(seed), depth: depth.max(1) }
+    }
+
+    fn search(&mut self, state: &CombatState, party_index: usize, depth: u32) -> f64 {
+        if depth == 0 || state.is_over() {
+            return evaluate(state, party_index);
+        }
+
+        let mut state = state.clone();
+        let actor = match state.next_combatant() {
+            Some(actor) => actor,
+            None => return evaluate(&state, party_index),
+        };
+        let scores = valid_events(&state, actor).into_iter().map(|event| {
+            let mut outcome = determinize(&state, &mut self.rng);
+            outcome.process_event(&event);
+            self.search(&outcome, party_index, depth - 1)
+        });
+
+        match actor.party_index == party_index {
+            true => scores.fold(f64::MIN, f64::max),
+            false => scores.fold(f64::MAX, f64::min),
+        }
+    }
+}
+
+impl Controller for LookaheadController {
+    fn choose(&mut self, state: &CombatState, actor: Target) -> CombatEvent {
+        let mut best = CombatEvent::SkipEvent;
+        let mut best_score = f64::MIN;
+
+        for event in valid_events(state, actor) {
+            let mut outcome = determinize(state, &mut self.rng);
+            outcome.process_event(&event);
+
+            let score = self.search(&outcome, actor.party_index, self.depth - 1);
+            if score > best_score {
+                best_score = score;
+                best = event;
+            }
+        }
+
+        best
+    }
+}
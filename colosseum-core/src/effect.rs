@@ -1,5 +1,6 @@
 // Copyright 2021 Chay Nabors.
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -9,6 +10,7 @@ use crate::combatant::Combatant;
 use crate::gender::Gender;
 use crate::lifetime::Lifetime;
 use crate::modifier::Modifier;
+use crate::modifier::ModifierTag;
 
 #[derive(Clone, Copy, Debug)]
 pub enum EffectSource<'a> {
@@ -17,14 +19,15 @@ pub enum EffectSource<'a> {
     Other(&'a Combatant),
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
 pub enum SubEffect {
+    Cleanse { tag: ModifierTag },
     Damage { aspect: Aspect, multiplier: f64 },
     DOT { aspect: Aspect, multiplier: f64, lifetime: Lifetime },
     Modifier { modifier: Modifier, attribute: Attribute },
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
 pub enum TargetFlag {
     Any,
     Gender(Gender),
@@ -44,7 +47,7 @@ impl TargetFlag {
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, Serialize)]
 pub enum TargetingScheme {
     All,
     MultiTarget(usize),
@@ -57,9 +60,17 @@ impl Default for TargetingScheme {
     }
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
 pub struct Effect {
     pub sub_effects: Vec<SubEffect>,
     pub target_flags: Vec<Vec<TargetFlag>>,
-    pub targeting_scheme: TargetingScheme,
+    /// Weapons default to their weapon type's scheme, anything else to targeting all
+    #[serde(default)]
+    pub targeting_scheme: Option<TargetingScheme>,
+}
+
+impl Effect {
+    pub fn targeting_scheme(&self) -> TargetingScheme {
+        self.targeting_scheme.unwrap_or_default()
+    }
 }
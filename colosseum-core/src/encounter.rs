@@ -0,0 +1,76 @@
+// Copyright 2021 Chay Nabors.
+
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::archetype::Archetype;
+use crate::archetype::ArchetypeIdentifier;
+use crate::combat_state::CombatState;
+use crate::content::Identifier;
+use crate::controller::ControllerKind;
+use crate::item::Item;
+use crate::item::ItemInstance;
+use crate::loot_table::LootTable;
+use crate::loot_table::LootTableIdentifier;
+use crate::modifier::AttributeModifier;
+use crate::party::Party;
+
+pub type EncounterIdentifier = Identifier<Encounter>;
+
+/// A scripted party of enemies for players to fight
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Encounter {
+    pub display_name: String,
+    pub description: String,
+    pub enemies: Vec<ArchetypeIdentifier>,
+    /// The bot that takes the enemies' turns
+    #[serde(default)]
+    pub controller: ControllerKind,
+    /// Modifiers every combatant on the field, player or enemy, enters combat with
+    #[serde(default)]
+    pub arena_modifiers: Vec<AttributeModifier>,
+    /// Rolled for the players when they win
+    #[serde(default)]
+    pub loot: Option<LootTableIdentifier>,
+}
+
+impl Encounter {
+    pub fn party(&self) -> Party {
+        Party {
+            members: self.enemies.iter().map(|enemy| <&Archetype>::from(*enemy).combatant()).collect(),
+            inventory: vec![],
+        }
+    }
+
+    /// The players' party against the enemies, with the arena modifiers applied to everyone
+    ///
+    /// Arena modifiers have no source, they belong to the arena rather than to any combatant
+    pub fn combat_state(&self, players: Party, seed: u64) -> CombatState {
+        let mut combat_state = CombatState::new(vec![players, self.party()], seed);
+        for member in combat_state.parties.iter_mut().flat_map(|party| &mut party.members) {
+            for attribute_modifier in &self.arena_modifiers {
+                member.apply_modifier(attribute_modifier.attribute, attribute_modifier.modifier.clone());
+            }
+            member.hp = member.hp.min(member.hp_max());
+        }
+
+        combat_state
+    }
+
+    /// Rolls the loot into the players' inventory if they won, returning the items won
+    pub fn award_loot<R: Rng>(&self, combat_state: &mut CombatState, rng: &mut R) -> Vec<ItemInstance<Item>> {
+        let loot_table = match self.loot {
+            Some(loot_table) => <&LootTable>::from(loot_table),
+            None => return vec![],
+        };
+        if combat_state.victor() != Some(0) {
+            return vec![];
+        }
+
+        let items = loot_table.roll(rng);
+        combat_state.parties[0].inventory.extend(items.iter().cloned());
+        items
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2021 Chay Nabors.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::aspect::Aspect;
+use crate::combatant::Combatant;
+use crate::content::Identifier;
+use crate::item::Item;
+use crate::modifier::AttributeModifier;
+
+pub type EquipmentSetIdentifier = Identifier<EquipmentSet>;
+
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
+pub struct SetBonus {
+    /// The number of set members that must be equipped for the bonus to apply
+    pub pieces: usize,
+    #[serde(default)]
+    pub modifiers: Vec<AttributeModifier>,
+    #[serde(default)]
+    pub fire_defense: f64,
+    #[serde(default)]
+    pub frost_defense: f64,
+    #[serde(default)]
+    pub lightning_defense: f64,
+    #[serde(default)]
+    pub physical_defense: f64,
+}
+
+impl SetBonus {
+    pub fn defense(&self, aspect: Aspect) -> f64 {
+        match aspect {
+            Aspect::Fire => self.fire_defense,
+            Aspect::Frost => self.frost_defense,
+            Aspect::Lightning => self.lightning_defense,
+            Aspect::Physical => self.physical_defense,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
+pub struct EquipmentSet {
+    pub display_name: String,
+    pub description: String,
+    pub members: Vec<Item>,
+    pub bonuses: Vec<SetBonus>,
+}
+
+impl EquipmentSet {
+    pub fn equipped_count(&self, combatant: &Combatant) -> usize {
+        let equipment = combatant.equipment();
+        self.members.iter().filter(|member| equipment.contains(member)).count()
+    }
+
+    pub fn active_bonuses(&self, combatant: &Combatant) -> impl Iterator<Item = &SetBonus> {
+        let equipped_count = self.equipped_count(combatant);
+        self.bonuses.iter().filter(move |bonus| bonus.pieces <= equipped_count)
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2021 Chay Nabors.
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+use crate::combat_event::CombatEvent;
+use crate::combat_state::CombatState;
+use crate::target::Target;
+
+/// A combat as it stood at the start of a turn, with the combatant readied to act
+#[derive(Clone, Debug)]
+struct Turn {
+    state: CombatState,
+    actor: Option<Target>,
+}
+
+impl Turn {
+    fn new(mut state: CombatState) -> Self {
+        let actor = ready(&mut state);
+        Self { state, actor }
+    }
+
+    fn advance(&mut self, event: &CombatEvent) -> Result<(), HistoryError> {
+        let actor = self.actor.ok_or(HistoryError::CombatOver)?;
+        if event.source().unwrap_or(actor) != actor {
+            return Err(HistoryError::OutOfTurn { expected: actor });
+        }
+
+        self.state.process_event(event);
+        self.actor = ready(&mut self.state);
+        Ok(())
+    }
+}
+
+fn ready(state: &mut CombatState) -> Option<Target> {
+    match state.is_over() {
+        true => None,
+        false => state.next_combatant(),
+    }
+}
+
+/// Event-sourced record of a combat that can step back and forth between turns and branch off alternate lines
+///
+/// Every `interval` turns the state is snapshotted and any other turn is rebuilt by reapplying events to the nearest
+/// snapshot before it, snapshots are shared between branches so branching copies little more than the event list
+#[derive(Clone, Debug)]
+pub struct CombatHistory {
+    interval: usize,
+    snapshots: Vec<Arc<Turn>>,
+    /// Every event of the current line, including those undone but not yet overwritten
+    events: Vec<CombatEvent>,
+    turn: usize,
+    current: Turn,
+}
+
+impl CombatHistory {
+    pub const DEFAULT_INTERVAL: usize = 16;
+
+    pub fn new(state: CombatState, interval: usize) -> Self {
+        let current = Turn::new(state);
+        Self { interval: interval.max(1), snapshots: vec![Arc::new(current.clone())], events: vec![], turn: 0, current }
+    }
+
+    /// The state at the current turn, with the acting combatant already readied
+    pub fn state(&self) -> &CombatState {
+        &self.current.state
+    }
+
+    /// The combatant acting on the current turn, none once combat is over
+    pub fn actor(&self) -> Option<Target> {
+        self.current.actor
+    }
+
+    /// The number of events applied to reach the current turn
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// The number of turns in the current line, counting those that can be redone
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// The events applied to reach the current turn
+    pub fn events(&self) -> &[CombatEvent] {
+        &self.events[..self.turn]
+    }
+
+    /// Performs an event on the current turn, discarding any undone events after it
+    pub fn push(&mut self, event: CombatEvent) -> Result<(), HistoryError> {
+        self.current.advance(&event)?;
+
+        self.events.truncate(self.turn);
+        self.snapshots.truncate(self.turn / self.interval + 1);
+        self.events.push(event);
+        self.turn += 1;
+
+        if self.turn.is_multiple_of(self.interval) {
+            self.snapshots.push(Arc::new(self.current.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Steps back a turn, returning whether there was one to step back to
+    pub fn undo(&mut self) -> bool {
+        match self.turn {
+            0 => false,
+            turn => self.seek(turn - 1).is_ok(),
+        }
+    }
+
+    /// Reapplies the next undone event, returning whether there was one
+    pub fn redo(&mut self) -> bool {
+        match self.turn < self.events.len() {
+            true => self.seek(self.turn + 1).is_ok(),
+            false => false,
+        }
+    }
+
+    /// Moves to any turn of the current line, rebuilding it from the nearest snapshot at or before it unless it lies
+    /// ahead of the current turn and no snapshot is closer
+    pub fn seek(&mut self, turn: usize) -> Result<(), HistoryError> {
+        if turn > self.events.len() {
+            return Err(HistoryError::NoSuchTurn(turn));
+        }
+
+        let snapshot = turn / self.interval;
+        if turn < self.turn || self.turn < snapshot * self.interval {
+            self.current = self.snapshots[snapshot].as_ref().clone();
+            self.turn = snapshot * self.interval;
+        }
+
+        for event in &self.events[self.turn..turn] {
+            self.current.advance(event)?;
+        }
+
+        self.turn = turn;
+        Ok(())
+    }
+
+    /// A separate history that shares this one's past up to the current turn, for exploring a different line
+    pub fn branch(&self) -> Self {
+        Self {
+            interval: self.interval,
+            snapshots: self.snapshots[..=self.turn / self.interval].to_vec(),
+            events: self.events[..self.turn].to_vec(),
+            turn: self.turn,
+            current: self.current.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HistoryError {
+    CombatOver,
+    NoSuchTurn(usize),
+    OutOfTurn { expected: Target },
+}
+
+impl Display for HistoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistoryError::CombatOver => write!(f, "combat is already over"),
+            HistoryError::NoSuchTurn(turn) => write!(f, "turn {} has not been played", turn),
+            HistoryError::OutOfTurn { expected } => write!(
+                f,
+                "event is out of turn, party {} member {} is acting",
+                expected.party_index, expected.member_index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
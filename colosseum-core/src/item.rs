@@ -1,23 +1,109 @@
 // Copyright 2021 Chay Nabors.
 
+use rand::Rng;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::accessory::AccessoryIdentifier;
+use crate::affix;
+use crate::affix::RolledAffix;
 use crate::bodywear::BodywearIdentifier;
 use crate::consumable::ConsumableIdentifier;
 use crate::footwear::FootwearIdentifier;
 use crate::handwear::HandwearIdentifier;
 use crate::headwear::HeadwearIdentifier;
 use crate::legwear::LegwearIdentifier;
+use crate::offhand::OffhandIdentifier;
 use crate::weapon::WeaponIdentifier;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
 pub enum Item {
+    Accessory(AccessoryIdentifier),
     Bodywear(BodywearIdentifier),
     Consumable(ConsumableIdentifier),
     Footwear(FootwearIdentifier),
     Handwear(HandwearIdentifier),
     Headwear(HeadwearIdentifier),
     Legwear(LegwearIdentifier),
+    Offhand(OffhandIdentifier),
     Weapon(WeaponIdentifier),
 }
+
+impl Item {
+    /// The slot the item is equipped to, consumables aren't equipped
+    pub fn slot(&self) -> Option<EquipmentSlot> {
+        match self {
+            Item::Accessory(_) => Some(EquipmentSlot::Accessory),
+            Item::Bodywear(_) => Some(EquipmentSlot::Bodywear),
+            Item::Consumable(_) => None,
+            Item::Footwear(_) => Some(EquipmentSlot::Footwear),
+            Item::Handwear(_) => Some(EquipmentSlot::Handwear),
+            Item::Headwear(_) => Some(EquipmentSlot::Headwear),
+            Item::Legwear(_) => Some(EquipmentSlot::Legwear),
+            Item::Offhand(_) => Some(EquipmentSlot::Offhand),
+            Item::Weapon(_) => Some(EquipmentSlot::Weapon),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub enum EquipmentSlot {
+    Accessory,
+    Bodywear,
+    Footwear,
+    Handwear,
+    Headwear,
+    Legwear,
+    Offhand,
+    Weapon,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Legendary,
+}
+
+impl Rarity {
+    pub fn affix_count(&self) -> usize {
+        match self {
+            Rarity::Common => 0,
+            Rarity::Uncommon => 1,
+            Rarity::Rare => 2,
+            Rarity::Legendary => 3,
+        }
+    }
+}
+
+/// A specific copy of a content item, carrying the affixes rolled for it
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct ItemInstance<T> {
+    pub base: T,
+    #[serde(default)]
+    pub rarity: Rarity,
+    #[serde(default)]
+    pub affixes: Vec<RolledAffix>,
+}
+
+impl<T> ItemInstance<T> {
+    /// A common instance without affixes
+    pub fn new(base: T) -> Self {
+        Self { base, rarity: Rarity::Common, affixes: vec![] }
+    }
+}
+
+impl ItemInstance<Item> {
+    /// Rolls affixes for the item from its slot's pool, items without a slot never roll affixes
+    pub fn roll<R: Rng>(base: Item, rarity: Rarity, rng: &mut R) -> Self {
+        let affixes = match base.slot() {
+            Some(slot) => affix::roll(slot, rarity, rng),
+            None => vec![],
+        };
+
+        Self { base, rarity, affixes }
+    }
+}
@@ -1,5 +1,8 @@
 // Copyright 2021 Chay Nabors.
 
+pub mod accessory;
+pub mod affix;
+pub mod archetype;
 pub mod aspect;
 pub mod attribute;
 #[path = "generated/bodywear.rs"]
@@ -8,8 +11,12 @@ pub mod combat_event;
 pub mod combat_state;
 pub mod combatant;
 pub mod consumable;
+pub mod content;
+pub mod controller;
 pub mod dot;
 pub mod effect;
+pub mod encounter;
+pub mod equipment_set;
 #[path = "generated/footwear.rs"]
 pub mod footwear;
 pub mod gender;
@@ -17,13 +24,20 @@ pub mod gender;
 pub mod handwear;
 #[path = "generated/headwear.rs"]
 pub mod headwear;
+pub mod history;
 pub mod item;
 #[path = "generated/legwear.rs"]
 pub mod legwear;
 pub mod lifetime;
+pub mod locale;
+pub mod loot_table;
+pub mod mcts;
 pub mod message;
 pub mod modifier;
+pub mod offhand;
 pub mod party;
+pub mod replay;
+pub mod roster;
 pub mod skill;
 pub mod target;
 pub mod weapon;
@@ -1,10 +1,28 @@
 // Copyright 2021 Chay Nabors.
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
 pub enum Lifetime {
     Active { duration: u32 },
     Constant,
 }
+
+impl Lifetime {
+    pub fn expired(&self) -> bool {
+        match self {
+            Lifetime::Active { duration } => *duration == 0,
+            Lifetime::Constant => false,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        if let Lifetime::Active { duration } = self {
+            if *duration > 0 {
+                *duration -= 1
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2021 Chay Nabors.
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::accessory::Accessory;
+use crate::affix::Affix;
+use crate::bodywear::Bodywear;
+use crate::consumable::Consumable;
+use crate::content::registry;
+use crate::content::Content;
+use crate::content::Identifier;
+use crate::encounter::Encounter;
+use crate::equipment_set::EquipmentSet;
+use crate::footwear::Footwear;
+use crate::handwear::Handwear;
+use crate::headwear::Headwear;
+use crate::item::Item;
+use crate::legwear::Legwear;
+use crate::offhand::Offhand;
+use crate::roster::Roster;
+use crate::skill::Skill;
+use crate::weapon::Weapon;
+
+/// The locale content text is written in, used when no table translates the text
+pub const DEFAULT_LOCALE: &str = "en";
+
+pub type StringTableIdentifier = Identifier<StringTable>;
+
+/// Translations into a single locale, any number of tables may translate into the same locale
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
+pub struct StringTable {
+    /// The locale translated into, a language optionally followed by a region, as in `pt` or `pt-BR`
+    pub locale: String,
+    /// Text of content entries keyed by category, then by key
+    #[serde(default)]
+    pub content: BTreeMap<String, BTreeMap<String, ContentText>>,
+    /// Interface text keyed by the id the client looks it up by
+    #[serde(default)]
+    pub interface: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
+pub struct ContentText {
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Content with text shown to players, written in the default locale
+pub trait Localized: Content {
+    fn display_name(&self) -> &str;
+    fn description(&self) -> &str;
+}
+
+macro_rules! localized {
+    ($($content:ty,)*) => {
+        $(
+            impl Localized for $content {
+                fn display_name(&self) -> &str {
+                    &self.display_name
+                }
+
+                fn description(&self) -> &str {
+                    &self.description
+                }
+            }
+        )*
+    };
+}
+
+localized! {
+    Accessory,
+    Affix,
+    Bodywear,
+    Consumable,
+    Encounter,
+    EquipmentSet,
+    Footwear,
+    Handwear,
+    Headwear,
+    Legwear,
+    Offhand,
+    Roster,
+    Skill,
+    Weapon,
+}
+
+/// Looks text up in a locale, falling back to the locale's language and then to the default locale
+#[derive(Clone, Debug)]
+pub struct Localizer {
+    locales: Vec<String>,
+}
+
+impl Localizer {
+    pub fn new(locale: &str) -> Self {
+        let mut locales = vec![locale.to_owned()];
+        if let Some((language, _)) = locale.split_once('-') {
+            locales.push(language.to_owned());
+        }
+        if !locales.iter().any(|locale| locale == DEFAULT_LOCALE) {
+            locales.push(DEFAULT_LOCALE.to_owned());
+        }
+
+        Self { locales }
+    }
+
+    /// The locales looked in, most specific first
+    pub fn locales(&self) -> &[String] {
+        &self.locales
+    }
+
+    pub fn display_name<T: Localized>(&self, identifier: Identifier<T>) -> &'static str {
+        self.content_text(identifier, |text| text.display_name.as_deref())
+            .unwrap_or_else(|| untranslated(identifier).display_name())
+    }
+
+    pub fn description<T: Localized>(&self, identifier: Identifier<T>) -> &'static str {
+        self.content_text(identifier, |text| text.description.as_deref())
+            .unwrap_or_else(|| untranslated(identifier).description())
+    }
+
+    /// The display name of the item's base
+    pub fn item_name(&self, item: Item) -> &'static str {
+        match item {
+            Item::Accessory(accessory) => self.display_name(accessory),
+            Item::Bodywear(bodywear) => self.display_name(bodywear),
+            Item::Consumable(consumable) => self.display_name(consumable),
+            Item::Footwear(footwear) => self.display_name(footwear),
+            Item::Handwear(handwear) => self.display_name(handwear),
+            Item::Headwear(headwear) => self.display_name(headwear),
+            Item::Legwear(legwear) => self.display_name(legwear),
+            Item::Offhand(offhand) => self.display_name(offhand),
+            Item::Weapon(weapon) => self.display_name(weapon),
+        }
+    }
+
+    /// Interface text, or the id itself when no locale has text for it
+    pub fn interface<'a>(&self, id: &'a str) -> &'a str {
+        self.tables().find_map(|table| table.interface.get(id).map(String::as_str)).unwrap_or(id)
+    }
+
+    fn content_text<T: Localized, F: Fn(&'static ContentText) -> Option<&'static str>>(
+        &self,
+        identifier: Identifier<T>,
+        text: F,
+    ) -> Option<&'static str> {
+        self.tables().filter_map(|table| table.content.get(T::CATEGORY)?.get(identifier.key())).find_map(text)
+    }
+
+    /// Every table of the installed registry in the order looked in
+    fn tables(&self) -> impl Iterator<Item = &'static StringTable> + '_ {
+        let tables = &registry().locales;
+        self.locales.iter().flat_map(move |locale| {
+            tables.identifiers().filter_map(|table| tables.get(table)).filter(move |table| &table.locale == locale)
+        })
+    }
+}
+
+fn untranslated<T: Localized>(identifier: Identifier<T>) -> &'static T {
+    T::store(registry()).get(identifier).unwrap_or_else(|| panic!("{} '{}' is not loaded", T::CATEGORY, identifier))
+}
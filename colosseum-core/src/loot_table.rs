@@ -0,0 +1,51 @@
+// Copyright 2021 Chay Nabors.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::content::Identifier;
+use crate::item::Item;
+use crate::item::ItemInstance;
+use crate::item::Rarity;
+
+pub type LootTableIdentifier = Identifier<LootTable>;
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct LootDrop {
+    pub item: Item,
+    #[serde(default)]
+    pub rarity: Rarity,
+    /// Relative likelihood of being dropped against the rest of the table
+    pub weight: u32,
+}
+
+/// Items awarded for winning an encounter
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+pub struct LootTable {
+    /// How many drops are rolled, the same drop may be rolled more than once
+    #[serde(default = "LootTable::default_rolls")]
+    pub rolls: u32,
+    pub drops: Vec<LootDrop>,
+}
+
+impl LootTable {
+    fn default_rolls() -> u32 {
+        1
+    }
+
+    /// Rolls the table's drops, equipment rolls its affixes as it drops
+    pub fn roll<R: Rng>(&self, rng: &mut R) -> Vec<ItemInstance<Item>> {
+        let mut items = vec![];
+        for _ in 0..self.rolls {
+            match self.drops.choose_weighted(rng, |drop| drop.weight) {
+                Ok(drop) => items.push(ItemInstance::roll(drop.item, drop.rarity, rng)),
+                Err(_) => break,
+            }
+        }
+
+        items
+    }
+}
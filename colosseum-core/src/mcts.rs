@@ -0,0 +1,244 @@
+// Copyright 2021 Chay Nabors.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use crate::combat_event::CombatEvent;
+use crate::combat_state::CombatState;
+use crate::combatant::Combatant;
+use crate::controller::determinize;
+use crate::controller::valid_events;
+use crate::controller::Controller;
+use crate::target::Target;
+
+/// Limits on how long a search runs, whichever is reached first ends it
+#[derive(Clone, Copy, Debug)]
+pub struct MctsBudget {
+    pub iterations: u32,
+    pub time: Option<Duration>,
+    /// Turns a playout runs for before the position is scored as it stands
+    pub rollout_turns: u32,
+    pub exploration: f64,
+}
+
+impl Default for MctsBudget {
+    fn default() -> Self {
+        Self { iterations: 2000, time: Some(Duration::from_secs(1)), rollout_turns: 40, exploration: 2f64.sqrt() }
+    }
+}
+
+/// Searches with Monte Carlo tree search, resampling the rng every iteration so it never relies on a known roll
+pub struct MctsController {
+    rng: Pcg32,
+    budget: MctsBudget,
+}
+
+impl MctsController {
+    pub fn new(seed: u64, budget: MctsBudget) -> Self {
+        Self { rng: Pcg32::seed_from_u64(seed), budget }
+    }
+
+    /// Grows a tree from the actor's turn until the budget runs out
+    fn search(&mut self, state: &CombatState, actor: Target) -> Node {
+        let started = Instant::now();
+        let mut root = Node::new(CombatEvent::SkipEvent);
+
+        for _ in 0..self.budget.iterations {
+            if matches!(self.budget.time, Some(time) if started.elapsed() >= time) {
+                break;
+            }
+
+            let mut state = determinize(state, &mut self.rng);
+            root.iterate(&mut state, actor, &mut self.rng, &self.budget);
+        }
+
+        root
+    }
+}
+
+impl Controller for MctsController {
+    fn choose(&mut self, state: &CombatState, actor: Target) -> CombatEvent {
+        self.search(state, actor)
+            .children
+            .into_iter()
+            .max_by_key(|child| child.visits)
+            .map(|child| child.event)
+            .unwrap_or(CombatEvent::SkipEvent)
+    }
+}
+
+/// An event and the statistics of every iteration that passed through it
+///
+/// The tree is open loop, children are found by event rather than by state, since chance makes the same line of
+/// events lead to different states between iterations
+struct Node {
+    event: CombatEvent,
+    visits: u32,
+    /// Summed reward for the party that performed the event
+    reward: f64,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(event: CombatEvent) -> Self {
+        Self { event, visits: 0, reward: 0., children: vec![] }
+    }
+
+    /// Selects down the tree, expands a single event and plays out from it, returning each party's reward
+    fn iterate(&mut self, state: &mut CombatState, actor: Target, rng: &mut Pcg32, budget: &MctsBudget) -> Vec<f64> {
+        let events = valid_events(state, actor);
+        let untried: Vec<&CombatEvent> =
+            events.iter().filter(|event| !self.children.iter().any(|child| &child.event == *event)).collect();
+
+        let (index, rewards) = match untried.choose(rng) {
+            Some(event) => {
+                self.children.push(Node::new((*event).clone()));
+                state.process_event(event);
+                (self.children.len() - 1, rollout(state, rng, budget.rollout_turns))
+            },
+            None => {
+                let available: Vec<usize> =
+                    (0..self.children.len()).filter(|i| events.contains(&self.children[*i].event)).collect();
+                let total = available.iter().map(|i| self.children[*i].visits).sum::<u32>().max(1) as f64;
+                let index = *available
+                    .iter()
+                    .max_by(|a, b| {
+                        let a = self.children[**a].upper_confidence_bound(total, budget.exploration);
+                        let b = self.children[**b].upper_confidence_bound(total, budget.exploration);
+                        a.total_cmp(&b)
+                    })
+                    .unwrap();
+
+                state.process_event(&self.children[index].event);
+                let next = match state.is_over() {
+                    true => None,
+                    false => state.next_combatant(),
+                };
+                let rewards = match next {
+                    Some(next) => self.children[index].iterate(state, next, rng, budget),
+                    None => rewards(state),
+                };
+
+                (index, rewards)
+            },
+        };
+
+        let child = &mut self.children[index];
+        child.visits += 1;
+        child.reward += rewards[actor.party_index];
+        rewards
+    }
+
+    fn upper_confidence_bound(&self, total_visits: f64, exploration: f64) -> f64 {
+        match self.visits {
+            0 => f64::MAX,
+            visits => {
+                let visits = visits as f64;
+                self.reward / visits + exploration * (total_visits.ln() / visits).sqrt()
+            },
+        }
+    }
+}
+
+/// Plays random non-skip events until combat ends or the turn limit is reached
+fn rollout(state: &mut CombatState, rng: &mut Pcg32, turns: u32) -> Vec<f64> {
+    for _ in 0..turns {
+        if state.is_over() {
+            break;
+        }
+
+        let actor = match state.next_combatant() {
+            Some(actor) => actor,
+            None => break,
+        };
+        let mut events = valid_events(state, actor);
+        let skip = events.pop().unwrap();
+        let event = events.choose(rng).cloned().unwrap_or(skip);
+        state.process_event(&event);
+    }
+
+    rewards(state)
+}
+
+/// Each party's reward between 0 and 1, all of it to the victor once combat is over, otherwise split by remaining hp
+fn rewards(state: &CombatState) -> Vec<f64> {
+    if state.is_over() {
+        return (0..state.parties.len())
+            .map(|party_index| (state.victor() == Some(party_index)) as u8 as f64)
+            .collect();
+    }
+
+    let shares: Vec<f64> =
+        state.parties.iter().map(|party| party.members.iter().map(Combatant::health).sum::<f64>()).collect();
+    let total = shares.iter().sum::<f64>();
+
+    shares.into_iter().map(|share| share / total).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use super::rewards;
+    use super::MctsBudget;
+    use super::MctsController;
+    use crate::combat_state::CombatState;
+    use crate::controller::valid_events;
+    use crate::controller::Controller;
+    use crate::roster::Roster;
+    use crate::roster::RosterIdentifier;
+    use crate::target::Target;
+
+    fn combat_state() -> (CombatState, Target) {
+        let party = |roster: &str| <&Roster>::from(roster.parse::<RosterIdentifier>().unwrap()).party();
+        let mut state = CombatState::new(vec![party("breakers"), party("bucklers")], 7);
+        let actor = state.next_combatant().unwrap();
+        (state, actor)
+    }
+
+    #[test]
+    fn stops_after_its_iterations() {
+        let (state, actor) = combat_state();
+        let budget = MctsBudget { iterations: 64, time: None, ..MctsBudget::default() };
+
+        let root = MctsController::new(7, budget).search(&state, actor);
+        assert_eq!(root.children.iter().map(|child| child.visits).sum::<u32>(), 64);
+    }
+
+    #[test]
+    fn stops_when_out_of_time() {
+        let (state, actor) = combat_state();
+        let time = Some(Duration::from_millis(50));
+        let budget = MctsBudget { iterations: u32::MAX, time, ..MctsBudget::default() };
+
+        let started = Instant::now();
+        let root = MctsController::new(7, budget).search(&state, actor);
+        // the budget is checked between iterations, so allow for one more to finish
+        assert!(started.elapsed() < Duration::from_millis(500), "searched for {:?}", started.elapsed());
+        assert!(root.children.iter().map(|child| child.visits).sum::<u32>() > 0);
+    }
+
+    #[test]
+    fn rewards_combatants_without_max_hp_as_out_of_health() {
+        let (mut state, _) = combat_state();
+        state.parties[1].members[0].vigor = 0.;
+
+        let rewards = rewards(&state);
+        assert!(rewards.iter().all(|reward| reward.is_finite()), "{:?}", rewards);
+        assert!((rewards.iter().sum::<f64>() - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn chooses_a_valid_event() {
+        let (state, actor) = combat_state();
+        let budget = MctsBudget { iterations: 64, time: None, ..MctsBudget::default() };
+
+        let event = MctsController::new(7, budget).choose(&state, actor);
+        assert!(valid_events(&state, actor).contains(&event), "{:?}", event);
+    }
+}
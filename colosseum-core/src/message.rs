@@ -1,84 +1,222 @@
 // Copyright 2021 Chay Nabors.
 
-use std::convert::TryFrom;
 use std::fmt::Display;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 
 use serde::Deserialize;
 use serde::Serialize;
 
 use crate::combat_event::CombatEvent;
 use crate::combat_state::CombatState;
+use crate::content;
+use crate::content::ContentFile;
+use crate::content::ContentManifest;
+use crate::encounter::EncounterIdentifier;
+use crate::item::Item;
+use crate::item::ItemInstance;
 use crate::party::Party;
+use crate::roster::RosterIdentifier;
 use crate::target::Target;
 
+/// The protocol spoken by this build, bumped with every change to [`Payload`]
+pub const PROTOCOL_VERSION: u32 = 6;
+
+/// Numbers the envelopes sent by this process
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// The handshake both sides open with
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Handshake {
+    pub content: ContentManifest,
+}
+
+impl Handshake {
+    /// The manifest of the installed content
+    pub fn current() -> Self {
+        Self { content: content::registry().manifest() }
+    }
+}
+
+/// The server's content, sent to clients whose content differs so they play with the same content as the server
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContentPush {
+    pub files: Vec<ContentFile>,
+}
+
+/// Why the server turned a client away, sent in place of its handshake
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct ProtocolVersion(pub u32);
+pub struct Rejection {
+    pub reason: String,
+}
+
+/// The rosters a server offers, sent once the handshake completes
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Rosters {
+    pub rosters: Vec<RosterIdentifier>,
+}
+
+/// The encounters a server hosts, sent once the handshake completes
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Encounters {
+    pub encounters: Vec<EncounterIdentifier>,
+}
+
+/// Readies a client with a party built from one of the server's rosters rather than a party of its own
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChooseRoster {
+    pub roster: RosterIdentifier,
+}
+
+/// Readies a client to fight one of the server's encounters, rather than another player
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChallengeEncounter {
+    pub encounter: EncounterIdentifier,
+    pub party: Party,
+}
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TakeTurn {
     pub target: Target,
+    /// Checksum of the server's state as the turn begins
+    pub checksum: u64,
+}
+
+/// A combat event relayed by the server, with the checksum of its state once the event is applied
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RelayedEvent {
+    pub event: CombatEvent,
+    pub checksum: u64,
 }
 
+/// Items won from an encounter, added to the party's inventory in the server's final state after the replay ends
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Victory;
+pub struct Loot {
+    pub items: Vec<ItemInstance<Item>>,
+}
 
-#[repr(u8)]
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub enum MessageType {
-    ProtocolVersion = 1, // this number must never change
-    CombatState = 20,
-    TakeTurn = 22,
-    CombatEvent = 23,
-    Victory = 30,
-    Party = 255,
+/// Every message sent between client and server
+///
+/// Payloads are encoded by variant index, so the protocol evolves only in ways older builds can still read:
+/// - new messages are added as new variants after every existing one
+/// - variants are never reordered or removed, a message no longer sent keeps its place
+/// - a message never changes shape once released, a changed message is added as a new variant instead
+/// - `Handshake` stays the first variant, so every build can open a connection with every other
+///
+/// Types carried in a message, such as [`CombatState`], [`Party`] and the content they hold, are part of its shape, so
+/// a change to any of them changes the message too
+///
+/// Any of these changes bumps [`PROTOCOL_VERSION`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Payload {
+    /// Both ways, opens the connection
+    Handshake(Handshake),
+    /// Server to client, before the server's handshake
+    ContentPush(ContentPush),
+    /// Server to client, after the server's handshake
+    Rosters(Rosters),
+    /// Server to client, after the server's handshake
+    Encounters(Encounters),
+    /// Client to server, readies the client with its own party
+    Party(Party),
+    /// Client to server, readies the client with a roster
+    ChooseRoster(ChooseRoster),
+    /// Client to server, starts a match against an encounter
+    ChallengeEncounter(ChallengeEncounter),
+    /// Server to client, as a match starts and on resync
+    CombatState(CombatState),
+    /// Server to client
+    TakeTurn(TakeTurn),
+    /// Client to server, the event taken for the turn
+    CombatEvent(CombatEvent),
+    /// Server to client
+    RelayedEvent(RelayedEvent),
+    /// Client to server, asks for the full combat state after a checksum mismatch
+    Resync,
+    /// Server to client, the match is over
+    Victory,
+    /// Server to client, after victory in an encounter
+    Loot(Loot),
+    /// Server to client, in place of the server's handshake when the client can't play on the server
+    Rejected(Rejection),
 }
 
-impl Display for MessageType {
+impl Display for Payload {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        let name = match self {
+            Payload::Handshake(_) => "Handshake",
+            Payload::ContentPush(_) => "ContentPush",
+            Payload::Rosters(_) => "Rosters",
+            Payload::Encounters(_) => "Encounters",
+            Payload::Party(_) => "Party",
+            Payload::ChooseRoster(_) => "ChooseRoster",
+            Payload::ChallengeEncounter(_) => "ChallengeEncounter",
+            Payload::CombatState(_) => "CombatState",
+            Payload::TakeTurn(_) => "TakeTurn",
+            Payload::CombatEvent(_) => "CombatEvent",
+            Payload::RelayedEvent(_) => "RelayedEvent",
+            Payload::Resync => "Resync",
+            Payload::Victory => "Victory",
+            Payload::Loot(_) => "Loot",
+            Payload::Rejected(_) => "Rejected",
+        };
+        write!(f, "{}", name)
     }
 }
 
+/// A payload as sent over the wire
+///
+/// The header, the version and then the sequence number, never changes shape, so it can be read from any build
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct Message {
-    pub type_: MessageType,
-    payload: Vec<u8>,
+pub struct Envelope {
+    pub version: u32,
+    /// Increases with every envelope the sender sends, so receivers can tell stale or repeated envelopes apart
+    pub sequence: u64,
+    pub payload: Payload,
 }
 
-macro_rules! try_into_message {
-    ($type:ident) => {
-        impl TryFrom<&$type> for Message {
-            type Error = bincode::Error;
-
-            fn try_from(value: &$type) -> Result<Self, Self::Error> {
-                Ok(Message { type_: MessageType::$type, payload: bincode::serialize(value)? })
-            }
-        }
-    };
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+    sequence: u64,
 }
 
-macro_rules! try_from_message {
-    ($type:ident) => {
-        impl TryFrom<&Message> for $type {
-            type Error = bincode::Error;
+impl Envelope {
+    /// Wraps the payload in this build's protocol, numbered after the last envelope sent by this process
+    pub fn new(payload: Payload) -> Self {
+        Self { version: PROTOCOL_VERSION, sequence: SEQUENCE.fetch_add(1, Ordering::Relaxed), payload }
+    }
 
-            fn try_from(value: &Message) -> Result<Self, Self::Error> {
-                bincode::deserialize(&value.payload)
-            }
-        }
-    };
+    pub fn encode(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        let header = bincode::deserialize::<Header>(bytes).map_err(EnvelopeError::Malformed)?;
+        bincode::deserialize(bytes).map_err(|error| match header.version > PROTOCOL_VERSION {
+            true => EnvelopeError::Unsupported { version: header.version, sequence: header.sequence },
+            false => EnvelopeError::Malformed(error),
+        })
+    }
 }
 
-macro_rules! message_conversions {
-    ($type:ident) => {
-        try_into_message!($type);
-        try_from_message!($type);
-    };
+#[derive(Debug)]
+pub enum EnvelopeError {
+    /// Not an envelope, or an envelope of this build's protocol or older that failed to decode
+    Malformed(bincode::Error),
+    /// An envelope of a newer protocol carrying a payload this build doesn't know
+    Unsupported { version: u32, sequence: u64 },
+}
+
+impl Display for EnvelopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnvelopeError::Malformed(error) => write!(f, "malformed envelope: {}", error),
+            EnvelopeError::Unsupported { version, sequence } => {
+                write!(f, "envelope {} uses protocol version {}, newer than {}", sequence, version, PROTOCOL_VERSION)
+            },
+        }
+    }
 }
 
-message_conversions!(ProtocolVersion);
-message_conversions!(CombatState);
-message_conversions!(TakeTurn);
-message_conversions!(CombatEvent);
-message_conversions!(Victory);
-message_conversions!(Party);
+impl std::error::Error for EnvelopeError {}
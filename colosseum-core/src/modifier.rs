@@ -1,18 +1,254 @@
 // Copyright 2021 Chay Nabors.
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::attribute::Attribute;
+use crate::consumable::ConsumableIdentifier;
 use crate::lifetime::Lifetime;
+use crate::skill::SkillIdentifier;
+use crate::target::Target;
+use crate::weapon::WeaponIdentifier;
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub enum ModifierExpression {
     Add(f64),
     Multiply(f64),
+    Override(f64),
+    Clamp {
+        min: f64,
+        max: f64,
+    },
+    /// Adds `percent` percent of another attribute's value
+    PercentOf {
+        attribute: Attribute,
+        percent: f64,
+    },
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+impl ModifierExpression {
+    /// The priority used by modifiers that don't specify one
+    pub fn default_priority(&self) -> i32 {
+        match self {
+            ModifierExpression::Override(_) => 0,
+            ModifierExpression::Add(_) | ModifierExpression::PercentOf { .. } => 100,
+            ModifierExpression::Multiply(_) => 200,
+            ModifierExpression::Clamp { .. } => 300,
+        }
+    }
+}
+
+/// The content a modifier was applied by
+#[derive(Clone, Copy, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub enum ModifierOrigin {
+    Consumable(ConsumableIdentifier),
+    Skill(SkillIdentifier),
+    Weapon(WeaponIdentifier),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub struct ModifierSource {
+    pub origin: ModifierOrigin,
+    pub applier: Target,
+}
+
+/// How a newly applied modifier interacts with modifiers of the same origin already on the attribute
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub enum Stacking {
+    /// Always add another instance
+    #[default]
+    Stack,
+    /// Reset the lifetime of existing instances, adding one only if none exist
+    Refresh,
+    /// Remove existing instances before adding
+    Replace,
+    /// Add instances up to the given count, then replace the oldest
+    MaxStacks(u32),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub enum ModifierTag {
+    Buff,
+    Debuff,
+    Magical,
+    Physical,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct Modifier {
     pub expression: ModifierExpression,
     pub lifetime: Lifetime,
+    /// Evaluation order, lowest first. Defaults to the expression's default priority
+    #[serde(default)]
+    pub priority: Option<i32>,
+    #[serde(default)]
+    pub stacking: Stacking,
+    #[serde(default)]
+    pub tags: Vec<ModifierTag>,
+    /// Set when the modifier is applied, content should leave this empty
+    #[serde(default)]
+    pub source: Option<ModifierSource>,
+}
+
+impl Modifier {
+    pub fn priority(&self) -> i32 {
+        self.priority.unwrap_or_else(|| self.expression.default_priority())
+    }
+
+    pub fn origin(&self) -> Option<ModifierOrigin> {
+        self.source.map(|source| source.origin)
+    }
+}
+
+/// A modifier bound to the attribute it modifies, used where modifiers are granted outside of effects
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct AttributeModifier {
+    pub attribute: Attribute,
+    pub modifier: Modifier,
+}
+
+/// Evaluates modifiers against an attribute's base value.
+///
+/// Modifiers are applied one at a time in ascending priority, with ties applied in the order given:
+/// - `Override` replaces the running value
+/// - `Add` adds to the running value
+/// - `PercentOf` adds a percentage of another attribute, resolved through `percent_of`. If that returns `None` the
+///   modifier is skipped
+/// - `Multiply` scales the running value
+/// - `Clamp` limits the running value to `min..=max`
+///
+/// The result is never negative.
+pub fn evaluate<'a, I, F>(base: f64, modifiers: I, percent_of: F) -> f64
+where
+    I: IntoIterator<Item = &'a Modifier>,
+    F: Fn(Attribute) -> Option<f64>,
+{
+    let mut modifiers: Vec<&Modifier> = modifiers.into_iter().collect();
+    modifiers.sort_by_key(|modifier| modifier.priority());
+
+    let mut value = base;
+    for modifier in modifiers {
+        match modifier.expression {
+            ModifierExpression::Add(add) => value += add,
+            ModifierExpression::Multiply(multiply) => value *= multiply,
+            ModifierExpression::Override(override_) => value = override_,
+            ModifierExpression::Clamp { min, max } => value = value.max(min).min(max),
+            ModifierExpression::PercentOf { attribute, percent } => {
+                if let Some(other) = percent_of(attribute) {
+                    value += other * percent / 100.
+                }
+            },
+        }
+    }
+
+    value.max(0.)
+}
+
+#[cfg(test)]
+mod test {
+    use super::evaluate;
+    use super::Modifier;
+    use super::ModifierExpression;
+    use super::ModifierExpression::*;
+    use crate::attribute::Attribute;
+    use crate::lifetime::Lifetime;
+    use crate::modifier::Stacking;
+
+    fn modifier(expression: ModifierExpression) -> Modifier {
+        Modifier {
+            expression,
+            lifetime: Lifetime::Constant,
+            priority: None,
+            stacking: Stacking::Stack,
+            tags: vec![],
+            source: None,
+        }
+    }
+
+    fn prioritized(expression: ModifierExpression, priority: i32) -> Modifier {
+        Modifier { priority: Some(priority), ..modifier(expression) }
+    }
+
+    fn strength_of_ten(attribute: Attribute) -> Option<f64> {
+        match attribute {
+            Attribute::Strength => Some(10.),
+            _ => None,
+        }
+    }
+
+    fn evaluate_all(base: f64, modifiers: &[Modifier]) -> f64 {
+        evaluate(base, modifiers, strength_of_ten)
+    }
+
+    #[test]
+    fn no_modifiers() {
+        assert_eq!(evaluate_all(7., &[]), 7.);
+    }
+
+    #[test]
+    fn single_expressions() {
+        assert_eq!(evaluate_all(10., &[modifier(Add(5.))]), 15.);
+        assert_eq!(evaluate_all(10., &[modifier(Add(-4.))]), 6.);
+        assert_eq!(evaluate_all(10., &[modifier(Multiply(1.5))]), 15.);
+        assert_eq!(evaluate_all(10., &[modifier(Override(3.))]), 3.);
+        assert_eq!(evaluate_all(10., &[modifier(Clamp { min: 0., max: 4. })]), 4.);
+        assert_eq!(evaluate_all(10., &[modifier(Clamp { min: 12., max: 20. })]), 12.);
+        assert_eq!(evaluate_all(10., &[modifier(PercentOf { attribute: Attribute::Strength, percent: 50. })]), 15.);
+    }
+
+    #[test]
+    fn never_negative() {
+        assert_eq!(evaluate_all(5., &[modifier(Add(-8.))]), 0.);
+        assert_eq!(evaluate_all(5., &[modifier(Multiply(-1.))]), 0.);
+        assert_eq!(evaluate_all(5., &[modifier(Override(-1.))]), 0.);
+    }
+
+    #[test]
+    fn unresolved_percent_of_is_skipped() {
+        let percent_of = modifier(PercentOf { attribute: Attribute::Agility, percent: 100. });
+        assert_eq!(evaluate_all(10., &[percent_of]), 10.);
+    }
+
+    #[test]
+    fn default_priority_order() {
+        let percent_of = PercentOf { attribute: Attribute::Strength, percent: 10. };
+        let clamp = Clamp { min: 0., max: 30. };
+
+        // every pair of expressions, listed in the reverse of their default order, and the expected result
+        let cases = [
+            (Add(2.), Override(5.), 7.),
+            (Multiply(2.), Override(5.), 10.),
+            (clamp, Override(50.), 30.),
+            (percent_of, Override(5.), 6.),
+            (Multiply(2.), Add(2.), 24.),
+            (clamp, Add(30.), 30.),
+            (Add(2.), percent_of, 13.),
+            (clamp, Multiply(4.), 30.),
+            (Multiply(2.), percent_of, 22.),
+            (clamp, percent_of, 11.),
+        ];
+
+        for (first, second, expected) in cases {
+            assert_eq!(evaluate_all(10., &[modifier(first), modifier(second)]), expected, "{:?} {:?}", first, second);
+            assert_eq!(evaluate_all(10., &[modifier(second), modifier(first)]), expected, "{:?} {:?}", second, first);
+        }
+    }
+
+    #[test]
+    fn explicit_priority_overrides_default() {
+        // add before override discards the add
+        assert_eq!(evaluate_all(10., &[prioritized(Add(2.), 0), prioritized(Override(5.), 1)]), 5.);
+        // clamp before multiply lets the multiply exceed the clamp
+        assert_eq!(evaluate_all(10., &[modifier(Multiply(2.)), prioritized(Clamp { min: 0., max: 12. }, 150)]), 20.);
+        // multiply before add scales only the base
+        assert_eq!(evaluate_all(10., &[modifier(Add(2.)), prioritized(Multiply(2.), 50)]), 22.);
+    }
+
+    #[test]
+    fn equal_priority_keeps_order() {
+        assert_eq!(evaluate_all(10., &[prioritized(Add(2.), 0), prioritized(Multiply(2.), 0)]), 24.);
+        assert_eq!(evaluate_all(10., &[prioritized(Multiply(2.), 0), prioritized(Add(2.), 0)]), 22.);
+        assert_eq!(evaluate_all(10., &[modifier(Override(1.)), modifier(Override(2.))]), 2.);
+    }
 }
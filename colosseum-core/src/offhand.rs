@@ -0,0 +1,59 @@
+// Copyright 2021 Chay Nabors.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::aspect::Aspect;
+use crate::content::Identifier;
+use crate::weapon::WeaponIdentifier;
+
+pub type OffhandIdentifier = Identifier<Offhand>;
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub enum OffhandKind {
+    /// Blocks incoming attacks entirely with the given probability
+    Shield { block_chance: f64 },
+    /// Attacks with the weapon after the main hand
+    Weapon(WeaponIdentifier),
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Offhand {
+    pub display_name: String,
+    pub description: String,
+    #[serde(default)]
+    pub fire_defense: f64,
+    #[serde(default)]
+    pub frost_defense: f64,
+    #[serde(default)]
+    pub lightning_defense: f64,
+    #[serde(default)]
+    pub physical_defense: f64,
+    pub kind: OffhandKind,
+}
+
+impl Offhand {
+    pub fn defense(&self, aspect: Aspect) -> f64 {
+        match aspect {
+            Aspect::Fire => self.fire_defense,
+            Aspect::Frost => self.frost_defense,
+            Aspect::Lightning => self.lightning_defense,
+            Aspect::Physical => self.physical_defense,
+        }
+    }
+
+    pub fn block_chance(&self) -> f64 {
+        match self.kind {
+            OffhandKind::Shield { block_chance } => block_chance,
+            OffhandKind::Weapon(_) => 0.,
+        }
+    }
+
+    pub fn weapon(&self) -> Option<WeaponIdentifier> {
+        match self.kind {
+            OffhandKind::Shield { .. } => None,
+            OffhandKind::Weapon(weapon) => Some(weapon),
+        }
+    }
+}
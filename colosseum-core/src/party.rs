@@ -1,13 +1,156 @@
 // Copyright 2021 Chay Nabors.
 
+use std::fmt::Display;
+
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::affix;
+use crate::affix::Affix;
+use crate::affix::AffixIdentifier;
+use crate::attribute::Attribute;
 use crate::combatant::Combatant;
 use crate::item::Item;
+use crate::item::ItemInstance;
+use crate::item::Rarity;
+use crate::weapon::Weapon;
+use crate::weapon::WeaponIdentifier;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Party {
     pub members: Vec<Combatant>,
-    pub inventory: Vec<Item>,
+    pub inventory: Vec<ItemInstance<Item>>,
+}
+
+impl Party {
+    /// Checks that the party is fit to enter combat
+    pub fn validate(&self) -> Result<(), PartyError> {
+        if self.members.is_empty() {
+            return Err(PartyError::NoMembers);
+        }
+
+        for member in &self.members {
+            for identifier in member.weapon_identifier().iter().chain(member.offhand_weapon().iter()) {
+                if let Some(requirement) = <&Weapon>::from(*identifier).unmet_requirements(member).next() {
+                    return Err(PartyError::WeaponRequirementUnmet {
+                        member: member.name.clone(),
+                        weapon: *identifier,
+                        attribute: requirement.attribute,
+                        required: requirement.value,
+                        actual: member.attribute_raw(requirement.attribute),
+                    });
+                }
+            }
+
+            for item in member.equipped_items() {
+                validate_affixes(&item)?;
+            }
+        }
+
+        for item in &self.inventory {
+            validate_affixes(item)?;
+        }
+
+        Ok(())
+    }
 }
+
+/// Checks that the item's affixes could have been rolled for it
+fn validate_affixes(item: &ItemInstance<Item>) -> Result<(), PartyError> {
+    let pool = match item.base.slot() {
+        Some(slot) => affix::pool(slot, item.rarity),
+        None => vec![],
+    };
+
+    let expected = item.rarity.affix_count().min(pool.len());
+    if item.affixes.len() != expected {
+        return Err(PartyError::AffixCountMismatch {
+            item: item.base,
+            rarity: item.rarity,
+            expected,
+            actual: item.affixes.len(),
+        });
+    }
+
+    for (index, rolled) in item.affixes.iter().enumerate() {
+        if !pool.contains(&rolled.affix) {
+            return Err(PartyError::AffixNotAllowed { item: item.base, rarity: item.rarity, affix: rolled.affix });
+        }
+
+        if item.affixes[..index].iter().any(|other| other.affix == rolled.affix) {
+            return Err(PartyError::DuplicateAffix { item: item.base, affix: rolled.affix });
+        }
+
+        let affix = <&Affix>::from(rolled.affix);
+        if !(affix.min..=affix.max).contains(&rolled.value) {
+            return Err(PartyError::AffixValueOutOfRange {
+                item: item.base,
+                affix: rolled.affix,
+                value: rolled.value,
+                min: affix.min,
+                max: affix.max,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PartyError {
+    NoMembers,
+    WeaponRequirementUnmet {
+        member: String,
+        weapon: WeaponIdentifier,
+        attribute: Attribute,
+        required: f64,
+        actual: f64,
+    },
+    AffixCountMismatch {
+        item: Item,
+        rarity: Rarity,
+        expected: usize,
+        actual: usize,
+    },
+    AffixNotAllowed {
+        item: Item,
+        rarity: Rarity,
+        affix: AffixIdentifier,
+    },
+    DuplicateAffix {
+        item: Item,
+        affix: AffixIdentifier,
+    },
+    AffixValueOutOfRange {
+        item: Item,
+        affix: AffixIdentifier,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+}
+
+impl Display for PartyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PartyError::NoMembers => write!(f, "party has no members"),
+            PartyError::WeaponRequirementUnmet { member, weapon, attribute, required, actual } => write!(
+                f,
+                "{} does not meet the {:?} requirement of {} ({} < {})",
+                member, attribute, weapon, actual, required
+            ),
+            PartyError::AffixCountMismatch { item, rarity, expected, actual } => {
+                write!(f, "{:?} {:?} has {} affixes instead of {}", rarity, item, actual, expected)
+            },
+            PartyError::AffixNotAllowed { item, rarity, affix } => {
+                write!(f, "{} can't roll on {:?} {:?}", affix, rarity, item)
+            },
+            PartyError::DuplicateAffix { item, affix } => write!(f, "{:?} has {} more than once", item, affix),
+            PartyError::AffixValueOutOfRange { item, affix, value, min, max } => {
+                write!(f, "{} on {:?} rolled {}, outside {}..={}", affix, item, value, min, max)
+            },
+        }
+    }
+}
+
+impl std::error::Error for PartyError {}
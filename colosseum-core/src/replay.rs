@@ -0,0 +1,183 @@
+// Copyright 2021 Chay Nabors.
+
+use std::fmt::Display;
+use std::io::Read;
+use std::io::Write;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat_event::CombatEvent;
+use crate::combat_state::CombatState;
+use crate::content;
+use crate::message::PROTOCOL_VERSION;
+
+/// The replay format written by this build, bumped whenever the layout of a replay changes
+pub const REPLAY_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplayHeader {
+    pub replay_version: u32,
+    pub protocol_version: u32,
+    pub content_version: String,
+    /// Seconds since the unix epoch at which the match started
+    pub started_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplayEvent {
+    /// Milliseconds since the match started
+    pub timestamp: u64,
+    pub event: CombatEvent,
+}
+
+/// Everything needed to play a match back, the state it started from and every event in the order it was applied
+///
+/// Replays are written as bincode with the header first, so the header can be read and checked on its own
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub initial_state: CombatState,
+    pub events: Vec<ReplayEvent>,
+    /// The state after the final event, absent if the match never finished
+    pub final_state: Option<CombatState>,
+}
+
+impl Replay {
+    pub fn new(initial_state: CombatState) -> Self {
+        Self {
+            header: ReplayHeader {
+                replay_version: REPLAY_VERSION,
+                protocol_version: PROTOCOL_VERSION,
+                content_version: content::registry().version().into(),
+                started_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            },
+            initial_state,
+            events: vec![],
+            final_state: None,
+        }
+    }
+
+    pub fn record(&mut self, elapsed: Duration, event: CombatEvent) {
+        self.events.push(ReplayEvent { timestamp: elapsed.as_millis() as u64, event });
+    }
+
+    pub fn finish(&mut self, final_state: CombatState) {
+        self.final_state = Some(final_state);
+    }
+
+    pub fn playback(&self) -> Playback<'_> {
+        Playback { replay: self, state: self.initial_state.clone(), position: 0 }
+    }
+
+    /// Plays every event back and checks the outcome against the recorded final state
+    pub fn verify(&self) -> Result<CombatState, ReplayError> {
+        let mut playback = self.playback();
+        while playback.step()?.is_some() {}
+
+        let final_state = self.final_state.as_ref().ok_or(ReplayError::Unfinished)?;
+        if final_state.checksum() != playback.state().checksum() {
+            return Err(ReplayError::FinalStateMismatch);
+        }
+
+        Ok(playback.state)
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), ReplayError> {
+        bincode::serialize_into(&mut writer, &self.header)?;
+        bincode::serialize_into(&mut writer, &self.initial_state)?;
+        bincode::serialize_into(&mut writer, &self.events)?;
+        bincode::serialize_into(&mut writer, &self.final_state)?;
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, ReplayError> {
+        let header: ReplayHeader = bincode::deserialize_from(&mut reader)?;
+        if header.replay_version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(header.replay_version));
+        }
+
+        Ok(Self {
+            header,
+            initial_state: bincode::deserialize_from(&mut reader)?,
+            events: bincode::deserialize_from(&mut reader)?,
+            final_state: bincode::deserialize_from(&mut reader)?,
+        })
+    }
+}
+
+/// Steps through a replay one event at a time, reproducing the turn order the server followed
+pub struct Playback<'a> {
+    replay: &'a Replay,
+    state: CombatState,
+    position: usize,
+}
+
+impl<'a> Playback<'a> {
+    pub fn state(&self) -> &CombatState {
+        &self.state
+    }
+
+    /// The index of the next event to be applied
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Applies the next event, checking it was performed by the combatant whose turn it was
+    pub fn step(&mut self) -> Result<Option<&'a ReplayEvent>, ReplayError> {
+        let replay_event = match self.replay.events.get(self.position) {
+            Some(replay_event) => replay_event,
+            None => return Ok(None),
+        };
+
+        let actor = match self.state.is_over() {
+            true => None,
+            false => self.state.next_combatant(),
+        };
+        let actor = actor.ok_or(ReplayError::EventAfterCombat(self.position))?;
+        if replay_event.event.source().unwrap_or(actor) != actor {
+            return Err(ReplayError::OutOfTurn(self.position));
+        }
+
+        self.state.process_event(&replay_event.event);
+        self.position += 1;
+
+        Ok(Some(replay_event))
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Encoding(bincode::Error),
+    EventAfterCombat(usize),
+    FinalStateMismatch,
+    OutOfTurn(usize),
+    Unfinished,
+    UnsupportedVersion(u32),
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Encoding(e) => write!(f, "replay could not be encoded or decoded: {}", e),
+            ReplayError::EventAfterCombat(index) => write!(f, "event {} happens after combat ended", index),
+            ReplayError::FinalStateMismatch => write!(f, "playback does not reach the recorded final state"),
+            ReplayError::OutOfTurn(index) => write!(f, "event {} is performed out of turn", index),
+            ReplayError::Unfinished => write!(f, "replay has no final state to verify against"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "replay version {} is not supported, expected {}", version, REPLAY_VERSION)
+            },
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<bincode::Error> for ReplayError {
+    fn from(e: bincode::Error) -> Self {
+        ReplayError::Encoding(e)
+    }
+}
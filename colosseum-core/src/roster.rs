@@ -0,0 +1,33 @@
+// Copyright 2021 Chay Nabors.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::archetype::Archetype;
+use crate::archetype::ArchetypeIdentifier;
+use crate::content::Identifier;
+use crate::item::Item;
+use crate::item::ItemInstance;
+use crate::party::Party;
+
+pub type RosterIdentifier = Identifier<Roster>;
+
+/// A ready made party the server offers to players
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Roster {
+    pub display_name: String,
+    pub description: String,
+    pub members: Vec<ArchetypeIdentifier>,
+    #[serde(default)]
+    pub inventory: Vec<ItemInstance<Item>>,
+}
+
+impl Roster {
+    pub fn party(&self) -> Party {
+        Party {
+            members: self.members.iter().map(|member| <&Archetype>::from(*member).combatant()).collect(),
+            inventory: self.inventory.clone(),
+        }
+    }
+}
@@ -1,15 +1,15 @@
 // Copyright 2021 Chay Nabors.
 
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::content::Identifier;
 use crate::effect::Effect;
 
-#[path = "generated/skill.rs"]
-mod skill;
-pub use skill::SkillIdentifier;
+pub type SkillIdentifier = Identifier<Skill>;
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
 pub struct Skill {
     pub display_name: String,
     pub description: String,
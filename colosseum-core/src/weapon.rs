@@ -1,16 +1,101 @@
 // Copyright 2021 Chay Nabors.
 
-use crate::effect::Effect;
-
-#[path = "generated/weapon.rs"]
-mod weapon;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
-pub use weapon::WeaponIdentifier;
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+use crate::attribute::Attribute;
+use crate::combatant::Combatant;
+use crate::content::Identifier;
+use crate::effect::Effect;
+use crate::effect::TargetingScheme;
+
+pub type WeaponIdentifier = Identifier<Weapon>;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub enum WeaponType {
+    Blade,
+    #[default]
+    Blunt,
+    Ranged,
+    Focus,
+}
+
+impl WeaponType {
+    /// How a weapon of this type targets when its effect doesn't say
+    pub fn targeting_scheme(&self) -> TargetingScheme {
+        match self {
+            WeaponType::Blade => TargetingScheme::MultiTarget(2),
+            WeaponType::Blunt => TargetingScheme::SingleTarget,
+            WeaponType::Ranged => TargetingScheme::SingleTarget,
+            WeaponType::Focus => TargetingScheme::All,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub enum ScalingGrade {
+    S,
+    A,
+    B,
+    C,
+    D,
+    E,
+}
+
+impl ScalingGrade {
+    /// The fraction of the scaled attribute added to the wielder's raw damage
+    pub fn coefficient(&self) -> f64 {
+        match self {
+            ScalingGrade::S => 1.,
+            ScalingGrade::A => 0.8,
+            ScalingGrade::B => 0.6,
+            ScalingGrade::C => 0.4,
+            ScalingGrade::D => 0.2,
+            ScalingGrade::E => 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct AttributeScaling {
+    pub attribute: Attribute,
+    pub grade: ScalingGrade,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct AttributeRequirement {
+    pub attribute: Attribute,
+    pub value: f64,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
 pub struct Weapon {
     pub display_name: String,
     pub description: String,
+    #[serde(default)]
+    pub weapon_type: WeaponType,
+    #[serde(default)]
+    pub scaling: Vec<AttributeScaling>,
+    #[serde(default)]
+    pub requirements: Vec<AttributeRequirement>,
     pub effect: Effect,
 }
+
+impl Weapon {
+    pub fn targeting_scheme(&self) -> TargetingScheme {
+        self.effect.targeting_scheme.unwrap_or_else(|| self.weapon_type.targeting_scheme())
+    }
+
+    /// Damage added to the wielder's raw damage before sub effect multipliers are applied
+    pub fn scaling_bonus(&self, wielder: &Combatant) -> f64 {
+        self.scaling.iter().map(|scaling| wielder.attribute(scaling.attribute) * scaling.grade.coefficient()).sum()
+    }
+
+    /// Requirements the wielder's unmodified attributes fall short of
+    pub fn unmet_requirements<'a>(&'a self, wielder: &'a Combatant) -> impl Iterator<Item = &'a AttributeRequirement> {
+        self.requirements
+            .iter()
+            .filter(move |requirement| wielder.attribute_raw(requirement.attribute) < requirement.value)
+    }
+}
@@ -0,0 +1,343 @@
+// Copyright 2021 Chay Nabors.
+
+//! Invariants of the combat engine checked against generated parties playing out generated valid events
+
+use colosseum_core::accessory::Accessory;
+use colosseum_core::affix;
+use colosseum_core::aspect::Aspect;
+use colosseum_core::bodywear::Bodywear;
+use colosseum_core::combat_event::CombatEvent;
+use colosseum_core::combat_state::CombatState;
+use colosseum_core::combatant::Combatant;
+use colosseum_core::consumable::ConsumableIdentifier;
+use colosseum_core::content::Content;
+use colosseum_core::content::Identifier;
+use colosseum_core::controller::evaluate;
+use colosseum_core::controller::is_valid_event;
+use colosseum_core::controller::valid_events;
+use colosseum_core::controller::Controller;
+use colosseum_core::controller::GreedyController;
+use colosseum_core::controller::LookaheadController;
+use colosseum_core::controller::RandomController;
+use colosseum_core::dot::DOT;
+use colosseum_core::footwear::Footwear;
+use colosseum_core::gender::Gender;
+use colosseum_core::handwear::Handwear;
+use colosseum_core::headwear::Headwear;
+use colosseum_core::history::CombatHistory;
+use colosseum_core::item::EquipmentSlot;
+use colosseum_core::item::Item;
+use colosseum_core::item::ItemInstance;
+use colosseum_core::item::Rarity;
+use colosseum_core::legwear::Legwear;
+use colosseum_core::lifetime::Lifetime;
+use colosseum_core::mcts::MctsBudget;
+use colosseum_core::mcts::MctsController;
+use colosseum_core::offhand::Offhand;
+use colosseum_core::party::Party;
+use colosseum_core::replay::Replay;
+use colosseum_core::skill::SkillIdentifier;
+use colosseum_core::target::Target;
+use colosseum_core::weapon::Weapon;
+use proptest::prelude::*;
+use proptest::sample::Index;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+const MAX_TURNS: usize = 200;
+
+/// A single turn of a played out combat
+struct Turn {
+    /// The state once the actor was readied, before it acted
+    readied: CombatState,
+    actor: Target,
+    event: CombatEvent,
+    /// The state once the event was processed
+    processed: CombatState,
+}
+
+/// Plays valid events picked by `choices` until combat ends or the choices run out
+fn play(mut state: CombatState, choices: &[Index]) -> Vec<Turn> {
+    let mut turns = vec![];
+
+    for choice in choices {
+        if state.is_over() {
+            break;
+        }
+
+        let actor = state.next_combatant().expect("combat that isn't over has a combatant able to act");
+        let readied = state.clone();
+        let events = valid_events(&state, actor);
+        let event = events[choice.index(events.len())].clone();
+
+        state.process_event(&event);
+        turns.push(Turn { readied, actor, event, processed: state.clone() });
+    }
+
+    turns
+}
+
+fn targets_mut(event: &mut CombatEvent) -> Option<&mut Vec<Target>> {
+    match event {
+        CombatEvent::AttackEvent { targets, .. }
+        | CombatEvent::ConsumableEvent { targets, .. }
+        | CombatEvent::SkillEvent { targets, .. } => Some(targets),
+        CombatEvent::SkipEvent => None,
+    }
+}
+
+fn attribute() -> impl Strategy<Value = f64> {
+    prop_oneof![1 => Just(0.), 9 => 0.0..30.0]
+}
+
+fn rarity() -> impl Strategy<Value = Rarity> {
+    prop::sample::select(vec![Rarity::Common, Rarity::Uncommon, Rarity::Rare, Rarity::Legendary])
+}
+
+fn instance<T: Content>(slot: EquipmentSlot) -> impl Strategy<Value = Option<ItemInstance<Identifier<T>>>> {
+    prop::option::of(
+        (prop::sample::select(Identifier::<T>::iter().collect::<Vec<_>>()), rarity(), any::<u64>()).prop_map(
+            move |(base, rarity, seed)| ItemInstance {
+                base,
+                rarity,
+                affixes: affix::roll(slot, rarity, &mut Pcg32::seed_from_u64(seed)),
+            },
+        ),
+    )
+}
+
+fn combatant() -> impl Strategy<Value = Combatant> {
+    let skills = SkillIdentifier::iter().collect::<Vec<_>>();
+    let genders = vec![Gender::None, Gender::Male, Gender::Female, Gender::Other];
+    let equipment = (
+        instance::<Bodywear>(EquipmentSlot::Bodywear),
+        instance::<Footwear>(EquipmentSlot::Footwear),
+        instance::<Handwear>(EquipmentSlot::Handwear),
+        instance::<Headwear>(EquipmentSlot::Headwear),
+        instance::<Legwear>(EquipmentSlot::Legwear),
+        instance::<Weapon>(EquipmentSlot::Weapon),
+        instance::<Offhand>(EquipmentSlot::Offhand),
+        instance::<Accessory>(EquipmentSlot::Accessory),
+    );
+
+    (
+        prop::sample::subsequence(skills.clone(), 0..=skills.len()),
+        prop::sample::select(genders),
+        prop::array::uniform7(attribute()),
+        equipment,
+        0.1..=1.0f64,
+    )
+        .prop_map(|(skills, gender, attributes, equipment, hp)| {
+            let [agility, dexterity, intelligence, mind, strength, vigor, vitality] = attributes;
+            let (bodywear, footwear, handwear, headwear, legwear, weapon, offhand, accessory) = equipment;
+
+            let mut combatant = Combatant {
+                name: "Generated".into(),
+                gender,
+                skills,
+                agility,
+                dexterity,
+                intelligence,
+                mind,
+                strength,
+                vigor,
+                vitality,
+                bodywear,
+                footwear,
+                handwear,
+                headwear,
+                legwear,
+                weapon,
+                offhand,
+                accessory,
+                hp: 0.,
+                fatigue: f64::MAX,
+                dots: vec![],
+                agility_modifiers: vec![],
+                dexterity_modifiers: vec![],
+                intelligence_modifiers: vec![],
+                mind_modifiers: vec![],
+                strength_modifiers: vec![],
+                vigor_modifiers: vec![],
+                vitality_modifiers: vec![],
+            };
+            combatant.hp = combatant.hp_max() * hp;
+            combatant
+        })
+}
+
+fn party() -> impl Strategy<Value = Party> {
+    let consumables = ConsumableIdentifier::iter().collect::<Vec<_>>();
+
+    (prop::collection::vec(combatant(), 1..=3), prop::sample::subsequence(consumables.clone(), 0..=consumables.len()))
+        .prop_map(|(members, consumables)| Party {
+            members,
+            inventory: consumables
+                .into_iter()
+                .map(|consumable| ItemInstance::new(Item::Consumable(consumable)))
+                .collect(),
+        })
+}
+
+fn combat_state() -> impl Strategy<Value = CombatState> {
+    (prop::collection::vec(party(), 2..=3), any::<u64>()).prop_map(|(parties, seed)| CombatState::new(parties, seed))
+}
+
+fn choices() -> impl Strategy<Value = Vec<Index>> {
+    prop::collection::vec(any::<Index>(), 0..MAX_TURNS)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn hp_is_never_negative_or_nan(state in combat_state(), choices in choices()) {
+        for turn in play(state, &choices) {
+            for member in turn.processed.parties.iter().flat_map(|party| &party.members) {
+                prop_assert!(!member.hp.is_nan(), "{} has NaN hp after {:?}", member.name, turn.event);
+                prop_assert!(member.hp >= 0., "{} has {} hp after {:?}", member.name, member.hp, turn.event);
+            }
+        }
+    }
+
+    #[test]
+    fn only_living_combatants_act(state in combat_state(), choices in choices()) {
+        for turn in play(state, &choices) {
+            let actor = &turn.readied.parties[turn.actor.party_index].members[turn.actor.member_index];
+            prop_assert!(actor.alive(), "{:?} was readied while dead", turn.actor);
+            prop_assert_eq!(turn.event.source().unwrap_or(turn.actor), turn.actor);
+        }
+    }
+
+    #[test]
+    fn next_combatant_terminates(state in combat_state(), kills in prop::collection::vec(any::<Index>(), 0..6)) {
+        let mut state = state;
+        let targets = state.get_target_list();
+        for kill in kills {
+            let target = targets[kill.index(targets.len())];
+            state.parties[target.party_index].members[target.member_index].hp = 0.;
+        }
+
+        match state.next_combatant() {
+            Some(actor) => prop_assert!(state.parties[actor.party_index].members[actor.member_index].alive()),
+            None => prop_assert!(state.is_over()),
+        }
+    }
+
+    #[test]
+    fn damage_over_time_deals_a_tick_per_turn_of_duration(
+        combatant in combatant(),
+        duration in 0..8u32,
+        damage in 0.0..20.0f64,
+    ) {
+        let mut state = CombatState::new(vec![Party { members: vec![combatant], inventory: vec![] }], 0);
+        let member = &mut state.parties[0].members[0];
+        member.dots.push(DOT { aspect: Aspect::Physical, damage_value: damage, lifetime: Lifetime::Active { duration } });
+        let per_tick = member.mitigate(Aspect::Physical, damage);
+        let mut expected = member.hp;
+
+        for turn in 1..=duration + 2 {
+            state.tick();
+            if turn <= duration {
+                expected = 0f64.max(expected - per_tick);
+            }
+
+            let member = &state.parties[0].members[0];
+            prop_assert_eq!(member.hp, expected, "after turn {} of {}", turn, duration);
+            prop_assert_eq!(member.dots.is_empty(), turn >= duration, "after turn {} of {}", turn, duration);
+        }
+    }
+
+    #[test]
+    fn serialization_preserves_state(state in combat_state(), choices in choices()) {
+        for turn in play(state, &choices) {
+            let bytes = bincode::serialize(&turn.processed).unwrap();
+            let decoded: CombatState = bincode::deserialize(&bytes).unwrap();
+            prop_assert_eq!(decoded.checksum(), turn.processed.checksum());
+        }
+    }
+
+    #[test]
+    fn replays_reproduce_combat(state in combat_state(), choices in choices()) {
+        let mut replay = Replay::new(state.clone());
+        let turns = play(state, &choices);
+        for turn in &turns {
+            replay.record(Default::default(), turn.event.clone());
+        }
+        replay.finish(turns.last().map_or(replay.initial_state.clone(), |turn| turn.processed.clone()));
+
+        let mut bytes = vec![];
+        replay.write(&mut bytes).unwrap();
+        prop_assert!(Replay::read(&bytes[..]).unwrap().verify().is_ok());
+    }
+
+    #[test]
+    fn history_rebuilds_every_turn(
+        state in combat_state(),
+        choices in choices(),
+        seeks in prop::collection::vec(any::<Index>(), 1..8),
+    ) {
+        let mut history = CombatHistory::new(state.clone(), 4);
+        let turns = play(state, &choices);
+        for turn in &turns {
+            history.push(turn.event.clone()).unwrap();
+        }
+
+        for seek in seeks {
+            let index = seek.index(turns.len() + 1);
+            history.seek(index).unwrap();
+            if let Some(turn) = turns.get(index) {
+                prop_assert_eq!(history.state().checksum(), turn.readied.checksum());
+            }
+        }
+    }
+
+    #[test]
+    fn only_valid_events_are_accepted(state in combat_state(), choices in choices()) {
+        for turn in play(state, &choices) {
+            let mut reversed = turn.event.clone();
+            if let Some(targets) = targets_mut(&mut reversed) {
+                targets.reverse();
+            }
+            prop_assert!(is_valid_event(&turn.readied, turn.actor, &reversed), "{:?}", reversed);
+
+            let mut out_of_range = turn.event.clone();
+            if let Some(targets) = targets_mut(&mut out_of_range) {
+                targets.push(Target { party_index: turn.readied.parties.len(), member_index: 0 });
+                prop_assert!(!is_valid_event(&turn.readied, turn.actor, &out_of_range), "{:?}", out_of_range);
+            }
+        }
+    }
+
+    #[test]
+    fn evaluations_are_finite(state in combat_state(), choices in choices()) {
+        for turn in play(state, &choices) {
+            for party_index in 0..turn.processed.parties.len() {
+                prop_assert!(evaluate(&turn.processed, party_index).is_finite());
+            }
+        }
+    }
+
+    #[test]
+    fn controllers_choose_valid_events(
+        state in combat_state(),
+        choices in prop::collection::vec(any::<Index>(), 0..16),
+        seed in any::<u64>(),
+    ) {
+        let budget = MctsBudget { iterations: 32, time: None, rollout_turns: 8, ..MctsBudget::default() };
+        let mut controllers: Vec<(&str, Box<dyn Controller>)> = vec![
+            ("random", Box::new(RandomController::new(seed))),
+            ("greedy", Box::new(GreedyController::new(seed))),
+            ("lookahead", Box::new(LookaheadController::new(seed, 1))),
+            ("mcts", Box::new(MctsController::new(seed, budget))),
+        ];
+
+        for turn in play(state, &choices) {
+            let events = valid_events(&turn.readied, turn.actor);
+            for (name, controller) in &mut controllers {
+                let event = controller.choose(&turn.readied, turn.actor);
+                prop_assert!(events.contains(&event), "{} chose {:?} for {:?}", name, event, turn.actor);
+            }
+        }
+    }
+}
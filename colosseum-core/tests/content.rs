@@ -0,0 +1,315 @@
+// Copyright 2021 Chay Nabors.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use colosseum_core::attribute::Attribute;
+use colosseum_core::combat_state::CombatState;
+use colosseum_core::content;
+use colosseum_core::content::ContentDifference;
+use colosseum_core::content::ContentError;
+use colosseum_core::content::ContentWatcher;
+use colosseum_core::content::Registry;
+use colosseum_core::encounter::Encounter;
+use colosseum_core::encounter::EncounterIdentifier;
+use colosseum_core::loot_table::LootTable;
+use colosseum_core::roster::Roster;
+use colosseum_core::roster::RosterIdentifier;
+use colosseum_core::weapon::WeaponIdentifier;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+/// A fresh directory under the system temp directory holding the given `<category>/<key>.json` files
+fn overlay(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("colosseum-content-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    for (path, contents) in files {
+        let path = directory.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    directory
+}
+
+const SKILL: &str = r#"{
+  "display_name": "Ember",
+  "description": "A smaller flame",
+  "effect": { "sub_effects": [], "target_flags": [["Any"]], "targeting_scheme": "SingleTarget" }
+}"#;
+
+#[test]
+fn overlays_add_and_replace_entries() {
+    let weapon = r#"{
+      "display_name": "Heavy Pipe Iron",
+      "description": "",
+      "weapon_type": "Blunt",
+      "effect": { "sub_effects": [], "target_flags": [], "targeting_scheme": "SingleTarget" }
+    }"#;
+    let directory = overlay("overlays", &[("skill/ember.json", SKILL), ("weapon/pipe_iron.json", weapon)]);
+
+    let embedded = Registry::embedded().unwrap();
+    let registry = Registry::load(&[&directory]).unwrap();
+    fs::remove_dir_all(directory).unwrap();
+
+    assert_eq!(registry.skills.len(), embedded.skills.len() + 1);
+    assert!(registry.skills.contains("ember"));
+    assert!(registry.skills.contains("scorch"));
+
+    let pipe_iron = "pipe_iron".parse::<WeaponIdentifier>().unwrap();
+    assert_eq!(registry.weapons.get(pipe_iron).unwrap().display_name, "Heavy Pipe Iron");
+    assert_ne!(registry.version(), embedded.version());
+}
+
+#[test]
+fn unknown_references_fail_to_load() {
+    let offhand = r#"{ "display_name": "Spare Spear", "description": "", "kind": { "Weapon": "spear" } }"#;
+    let directory = overlay("unknown", &[("offhand/spare_spear.json", offhand)]);
+
+    let loaded = Registry::load(&[&directory]);
+    fs::remove_dir_all(directory).unwrap();
+
+    match loaded {
+        Err(ContentError::Parse { path, error }) => {
+            assert!(path.ends_with("offhand/spare_spear.json"));
+            assert!(error.to_string().contains("unknown weapon 'spear'"));
+        },
+        other => panic!("expected an unknown weapon error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn watcher_installs_valid_changes_only() {
+    let directory = overlay("watcher", &[]);
+    let mut watcher = ContentWatcher::new(vec![directory.clone()], Duration::ZERO);
+    assert!(watcher.poll().is_none());
+
+    fs::create_dir_all(directory.join("skill")).unwrap();
+    fs::write(directory.join("skill/ember.json"), SKILL).unwrap();
+    let registry = watcher.poll().unwrap().unwrap();
+    assert!(registry.skills.contains("ember"));
+    assert!(content::registry().skills.contains("ember"));
+
+    fs::write(directory.join("skill/ember.json"), "{ \"display_name\": ").unwrap();
+    assert!(matches!(watcher.poll(), Some(Err(ContentError::Parse { .. }))));
+    assert_eq!(content::registry().version(), registry.version());
+
+    fs::remove_file(directory.join("skill/ember.json")).unwrap();
+    assert!(matches!(watcher.poll(), Some(Err(ContentError::Removed { category: "skill", key: "ember" }))));
+    assert!(content::registry().skills.contains("ember"));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn lenient_loading_skips_broken_files() {
+    let offhand = r#"{ "display_name": "Spare Spear", "description": "", "kind": { "Weapon": "spear" } }"#;
+    let directory = overlay(
+        "lenient",
+        &[
+            ("skill/ember.json", SKILL),
+            ("skill/cinder.json", "{ \"display_name\": "),
+            ("offhand/spare_spear.json", offhand),
+        ],
+    );
+
+    let (registry, errors) = Registry::load_lenient(&[&directory]);
+    fs::remove_dir_all(directory).unwrap();
+
+    assert_eq!(errors.len(), 2);
+    assert!(errors.iter().all(|error| matches!(error, ContentError::Parse { .. })));
+    assert!(registry.skills.contains("ember"));
+    assert!(!registry.skills.contains("cinder"));
+    assert!(registry.files().iter().any(|file| file.path.ends_with("skill/ember.json")));
+}
+
+#[test]
+fn lenient_loading_drops_entries_referring_to_broken_files() {
+    let roster = r#"{ "display_name": "Brawlers", "description": "", "members": ["brawler"] }"#;
+    let directory =
+        overlay("dangling", &[("combatant/brawler.json", "{ \"name\": "), ("roster/brawlers.json", roster)]);
+
+    let (registry, errors) = Registry::load_lenient(&[&directory]);
+    fs::remove_dir_all(directory).unwrap();
+
+    assert_eq!(errors.len(), 2);
+    match &errors[1] {
+        ContentError::Parse { path, error } => {
+            assert!(path.ends_with("roster/brawlers.json"));
+            assert!(error.to_string().contains("unknown combatant 'brawler'"));
+        },
+        error => panic!("expected an unknown combatant error, got {:?}", error),
+    }
+    assert!(!registry.rosters.contains("brawlers"));
+    assert!(registry.rosters.contains("breakers"));
+    assert!(registry.files().iter().all(|file| file.key != "brawlers"));
+}
+
+#[test]
+fn schemas_describe_every_category() {
+    let schemas = content::schemas();
+    let categories: Vec<&str> = schemas.iter().map(|(category, _)| *category).collect();
+    for (category, _) in content::registry().keys() {
+        assert!(categories.contains(&category), "no schema for {}", category);
+    }
+
+    let (_, offhand) = schemas.iter().find(|(category, _)| *category == "offhand").unwrap();
+    let offhand = serde_json::to_value(offhand).unwrap();
+    let weapons = &offhand["definitions"]["WeaponIdentifier"]["enum"];
+    assert!(weapons.as_array().unwrap().contains(&"pipe_iron".into()));
+}
+
+#[test]
+fn identifiers_are_sent_as_keys() {
+    let pipe_iron = "pipe_iron".parse::<WeaponIdentifier>().unwrap();
+    let encoded = bincode::serialize(&pipe_iron).unwrap();
+    assert_eq!(encoded, bincode::serialize("pipe_iron").unwrap());
+    assert_eq!(bincode::deserialize::<WeaponIdentifier>(&encoded).unwrap(), pipe_iron);
+
+    let error = bincode::deserialize::<WeaponIdentifier>(&bincode::serialize("spear").unwrap()).unwrap_err();
+    assert!(error.to_string().contains("unknown weapon 'spear'"));
+}
+
+#[test]
+fn stores_hold_any_number_of_entries() {
+    let files: Vec<(String, &str)> = (0..300).map(|i| (format!("skill/ember_{:03}.json", i), SKILL)).collect();
+    let files: Vec<(&str, &str)> = files.iter().map(|(path, contents)| (path.as_str(), *contents)).collect();
+    let directory = overlay("many", &files);
+
+    let registry = Registry::load(&[&directory]).unwrap();
+    fs::remove_dir_all(directory).unwrap();
+
+    assert_eq!(registry.skills.len(), Registry::embedded().unwrap().skills.len() + 300);
+    assert!(registry.skills.contains("ember_299"));
+}
+
+#[test]
+fn manifests_report_differing_entries() {
+    let weapon = r#"{
+      "display_name": "Heavy Pipe Iron",
+      "description": "",
+      "weapon_type": "Blunt",
+      "effect": { "sub_effects": [], "target_flags": [], "targeting_scheme": "SingleTarget" }
+    }"#;
+    let directory = overlay("manifest", &[("skill/ember.json", SKILL), ("weapon/pipe_iron.json", weapon)]);
+
+    let embedded = Registry::embedded().unwrap();
+    let registry = Registry::load(&[&directory]).unwrap();
+    fs::remove_dir_all(directory).unwrap();
+
+    assert!(embedded.manifest().differences(&Registry::embedded().unwrap().manifest()).is_empty());
+
+    assert_eq!(
+        embedded.manifest().differences(&registry.manifest()),
+        vec![
+            ContentDifference::OnlyTheirs { category: "skill".into(), key: "ember".into() },
+            ContentDifference::Changed { category: "weapon".into(), key: "pipe_iron".into() },
+        ]
+    );
+
+    let pushed = Registry::from_pushed(registry.files().to_vec()).unwrap();
+    assert_eq!(pushed.version(), registry.version());
+    assert!(pushed.manifest().differences(&registry.manifest()).is_empty());
+}
+
+#[test]
+fn formats_load_alike() {
+    let ron = r#"(
+        display_name: "Ember",
+        description: "A smaller flame",
+        effect: (sub_effects: [], target_flags: [[Any]], targeting_scheme: SingleTarget),
+    )"#;
+    let toml = r#"
+        display_name = "Ember"
+        description = "A smaller flame"
+        effect = { sub_effects = [], target_flags = [["Any"]], targeting_scheme = "SingleTarget" }
+    "#;
+    let directory = overlay(
+        "formats",
+        &[("skill/ember.json", SKILL), ("skill/ember_ron.ron", ron), ("skill/ember_toml.toml", toml)],
+    );
+
+    let registry = Registry::load(&[&directory]).unwrap();
+    fs::remove_dir_all(directory).unwrap();
+
+    let skill = |key: &str| {
+        let identifier = registry.skills.identifiers().find(|identifier| identifier.key() == key).unwrap();
+        serde_json::to_value(registry.skills.get(identifier).unwrap()).unwrap()
+    };
+    assert_eq!(skill("ember_ron"), skill("ember"));
+    assert_eq!(skill("ember_toml"), skill("ember"));
+}
+
+#[test]
+fn entries_are_defined_once_per_directory() {
+    let toml = r#"display_name = "Ember""#;
+    let directory = overlay("duplicate", &[("skill/ember.json", SKILL), ("skill/ember.toml", toml)]);
+
+    let loaded = Registry::load(&[&directory]);
+    fs::remove_dir_all(directory).unwrap();
+
+    match loaded {
+        Err(ContentError::Duplicate { path, other }) => {
+            assert!(path.ends_with("skill/ember.toml"));
+            assert!(other.ends_with("skill/ember.json"));
+        },
+        other => panic!("expected a duplicate entry error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn rosters_build_parties_of_archetypes() {
+    for roster in RosterIdentifier::iter() {
+        let party = <&Roster>::from(roster).party();
+        party.validate().unwrap();
+        for member in &party.members {
+            assert_eq!(member.hp, member.hp_max());
+            assert_eq!(member.fatigue, f64::MAX);
+        }
+    }
+
+    let breakers = <&Roster>::from("breakers".parse::<RosterIdentifier>().unwrap()).party();
+    assert_eq!(breakers.members[0].name, "Angelo");
+    assert_eq!(breakers.members[0].hp, 20.);
+}
+
+#[test]
+fn encounters_field_enemies_in_their_arena() {
+    let encounter = <&Encounter>::from("flooded_alley".parse::<EncounterIdentifier>().unwrap());
+    let players = <&Roster>::from("breakers".parse::<RosterIdentifier>().unwrap()).party();
+    let combat_state = encounter.combat_state(players.clone(), 0);
+    let outside = CombatState::new(vec![players, encounter.party()], 0);
+
+    assert_eq!(combat_state.parties.len(), 2);
+    assert_eq!(combat_state.parties[1].members.len(), encounter.enemies.len());
+    let members = combat_state.parties.iter().flat_map(|party| &party.members);
+    for (member, outside) in members.zip(outside.parties.iter().flat_map(|party| &party.members)) {
+        assert!((member.attribute(Attribute::Agility) - outside.attribute(Attribute::Agility) * 0.8).abs() < 1e-9);
+    }
+
+    let loot_table = <&LootTable>::from(encounter.loot.unwrap());
+    let items = loot_table.roll(&mut Pcg32::seed_from_u64(0));
+    assert_eq!(items.len(), loot_table.rolls as usize);
+    assert!(items.iter().all(|item| loot_table.drops.iter().any(|drop| drop.item == item.base)));
+}
+
+#[test]
+fn encounters_award_loot_to_winners() {
+    let encounter = <&Encounter>::from("flooded_alley".parse::<EncounterIdentifier>().unwrap());
+    let players = <&Roster>::from("breakers".parse::<RosterIdentifier>().unwrap()).party();
+    let rolls = <&LootTable>::from(encounter.loot.unwrap()).rolls as usize;
+
+    let mut won = encounter.combat_state(players.clone(), 0);
+    let carried = won.parties[0].inventory.len();
+    won.parties[1].members.iter_mut().for_each(|enemy| enemy.hp = 0.);
+    let items = encounter.award_loot(&mut won, &mut Pcg32::seed_from_u64(0));
+    assert_eq!(items.len(), rolls);
+    assert_eq!(won.parties[0].inventory.len(), carried + rolls);
+    assert_eq!(won.parties[0].inventory[carried..], items[..]);
+
+    let mut lost = encounter.combat_state(players, 0);
+    lost.parties[0].members.iter_mut().for_each(|player| player.hp = 0.);
+    assert!(encounter.award_loot(&mut lost, &mut Pcg32::seed_from_u64(0)).is_empty());
+    assert_eq!(lost.parties[0].inventory.len(), carried);
+}
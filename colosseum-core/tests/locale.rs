@@ -0,0 +1,59 @@
+// Copyright 2021 Chay Nabors.
+
+use std::fs;
+
+use colosseum_core::content;
+use colosseum_core::content::Registry;
+use colosseum_core::item::Item;
+use colosseum_core::locale::Localizer;
+use colosseum_core::skill::SkillIdentifier;
+
+#[test]
+fn text_falls_back_through_locales() {
+    let directory = std::env::temp_dir().join(format!("colosseum-locale-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(directory.join("locale")).unwrap();
+    fs::write(
+        directory.join("locale/pt.json"),
+        r#"{
+          "locale": "pt",
+          "content": {
+            "skill": { "sweep": { "display_name": "Varrer", "description": "Ataca três alvos" } },
+            "weapon": { "pipe_iron": { "display_name": "Cano de Ferro" } }
+          },
+          "interface": { "menu.play": "Jogar", "menu.quit": "Sair" }
+        }"#,
+    )
+    .unwrap();
+    fs::write(
+        directory.join("locale/pt_br.toml"),
+        r#"
+            locale = "pt-BR"
+            content = { skill = { sweep = { display_name = "Varrida" } } }
+            interface = { "menu.quit" = "Fechar" }
+        "#,
+    )
+    .unwrap();
+
+    content::install(Registry::load(&[&directory]).unwrap());
+    fs::remove_dir_all(directory).unwrap();
+
+    let sweep = "sweep".parse::<SkillIdentifier>().unwrap();
+    let scorch = "scorch".parse::<SkillIdentifier>().unwrap();
+
+    let brazilian = Localizer::new("pt-BR");
+    assert_eq!(brazilian.locales(), ["pt-BR", "pt", "en"]);
+    assert_eq!(brazilian.display_name(sweep), "Varrida");
+    assert_eq!(brazilian.description(sweep), "Ataca três alvos");
+    assert_eq!(brazilian.display_name(scorch), "Scorch");
+    assert_eq!(brazilian.item_name(Item::Weapon("pipe_iron".parse().unwrap())), "Cano de Ferro");
+    assert_eq!(brazilian.item_name(Item::Consumable("grenade".parse().unwrap())), "Grenade");
+    assert_eq!(brazilian.interface("menu.quit"), "Fechar");
+    assert_eq!(brazilian.interface("menu.play"), "Jogar");
+    assert_eq!(brazilian.interface("menu.options"), "menu.options");
+    assert_eq!(brazilian.interface("action.skip"), "Skip");
+
+    let english = Localizer::new("en");
+    assert_eq!(english.locales(), ["en"]);
+    assert_eq!(english.display_name(sweep), "Sweep");
+}
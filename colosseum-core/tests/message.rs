@@ -0,0 +1,53 @@
+// Copyright 2021 Chay Nabors.
+
+use colosseum_core::message::Envelope;
+use colosseum_core::message::EnvelopeError;
+use colosseum_core::message::Handshake;
+use colosseum_core::message::Payload;
+use colosseum_core::message::Rejection;
+use colosseum_core::message::TakeTurn;
+use colosseum_core::message::PROTOCOL_VERSION;
+use colosseum_core::target::Target;
+
+#[test]
+fn envelopes_round_trip_in_sequence() {
+    let first = Envelope::new(Payload::Handshake(Handshake::current()));
+    let target = Target { party_index: 1, member_index: 0 };
+    let second = Envelope::new(Payload::TakeTurn(TakeTurn { target, checksum: 7 }));
+    assert!(second.sequence > first.sequence);
+
+    let decoded = Envelope::decode(&second.encode().unwrap()).unwrap();
+    assert_eq!(decoded.version, PROTOCOL_VERSION);
+    assert_eq!(decoded.sequence, second.sequence);
+    match decoded.payload {
+        Payload::TakeTurn(take_turn) => assert_eq!((take_turn.target, take_turn.checksum), (target, 7)),
+        payload => panic!("decoded {} instead of TakeTurn", payload),
+    }
+}
+
+#[test]
+fn rejections_carry_their_reason() {
+    let reason = "content differs from the server's version 1 in 2 entries".to_string();
+    let rejection = Envelope::new(Payload::Rejected(Rejection { reason: reason.clone() }));
+
+    match Envelope::decode(&rejection.encode().unwrap()).unwrap().payload {
+        Payload::Rejected(decoded) => assert_eq!(decoded.reason, reason),
+        payload => panic!("decoded {} instead of Rejected", payload),
+    }
+}
+
+#[test]
+fn unknown_payloads_report_their_version() {
+    // the header of an envelope from a later protocol, carrying a variant this build doesn't have
+    let from_later = bincode::serialize(&(PROTOCOL_VERSION + 1, 3u64, u32::MAX)).unwrap();
+    match Envelope::decode(&from_later) {
+        Err(EnvelopeError::Unsupported { version, sequence }) => {
+            assert_eq!((version, sequence), (PROTOCOL_VERSION + 1, 3))
+        },
+        result => panic!("expected an unsupported envelope, got {:?}", result),
+    }
+
+    let from_ours = bincode::serialize(&(PROTOCOL_VERSION, 3u64, u32::MAX)).unwrap();
+    assert!(matches!(Envelope::decode(&from_ours), Err(EnvelopeError::Malformed(_))));
+    assert!(matches!(Envelope::decode(&[1, 2]), Err(EnvelopeError::Malformed(_))));
+}
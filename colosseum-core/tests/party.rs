@@ -0,0 +1,181 @@
+// Copyright 2021 Chay Nabors.
+
+use colosseum_core::affix::RolledAffix;
+use colosseum_core::archetype::Archetype;
+use colosseum_core::attribute::Attribute;
+use colosseum_core::bodywear::BodywearIdentifier;
+use colosseum_core::combatant::Combatant;
+use colosseum_core::effect::TargetingScheme;
+use colosseum_core::item::Item;
+use colosseum_core::item::ItemInstance;
+use colosseum_core::item::Rarity;
+use colosseum_core::lifetime::Lifetime;
+use colosseum_core::modifier::Modifier;
+use colosseum_core::modifier::ModifierExpression;
+use colosseum_core::modifier::Stacking;
+use colosseum_core::party::Party;
+use colosseum_core::party::PartyError;
+use colosseum_core::weapon::Weapon;
+use colosseum_core::weapon::WeaponIdentifier;
+use colosseum_core::weapon::WeaponType;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+fn wielder(strength: f64) -> Combatant {
+    let archetype: Archetype = serde_json::from_str(&format!(
+        r#"{{
+          "name": "Tester",
+          "gender": "None",
+          "agility": 10, "dexterity": 10, "intelligence": 10, "mind": 10,
+          "strength": {}, "vigor": 10, "vitality": 10,
+          "weapon": {{ "base": "pipe_iron" }}
+        }}"#,
+        strength
+    ))
+    .unwrap();
+    archetype.combatant()
+}
+
+fn strength_bonus(value: f64) -> Modifier {
+    Modifier {
+        expression: ModifierExpression::Add(value),
+        lifetime: Lifetime::Constant,
+        priority: None,
+        stacking: Stacking::Stack,
+        tags: vec![],
+        source: None,
+    }
+}
+
+fn party(member: Combatant) -> Party {
+    Party { members: vec![member], inventory: vec![] }
+}
+
+fn pipe_iron() -> WeaponIdentifier {
+    "pipe_iron".parse().unwrap()
+}
+
+#[test]
+fn weapons_scale_with_modified_attributes() {
+    // pipe iron scales with strength at grade C
+    let weapon = <&Weapon>::from(pipe_iron());
+    let mut wielder = wielder(10.);
+    assert_eq!(weapon.scaling_bonus(&wielder), 4.);
+
+    wielder.apply_modifier(Attribute::Strength, strength_bonus(5.));
+    assert_eq!(weapon.scaling_bonus(&wielder), 6.);
+}
+
+#[test]
+fn weapon_requirements_are_enforced() {
+    // pipe iron requires 4 strength
+    assert_eq!(party(wielder(4.)).validate(), Ok(()));
+
+    let mut weak = wielder(3.);
+    assert_eq!(<&Weapon>::from(pipe_iron()).unmet_requirements(&weak).count(), 1);
+
+    // modifiers don't count towards requirements
+    weak.apply_modifier(Attribute::Strength, strength_bonus(5.));
+    assert_eq!(
+        party(weak).validate(),
+        Err(PartyError::WeaponRequirementUnmet {
+            member: "Tester".to_string(),
+            weapon: pipe_iron(),
+            attribute: Attribute::Strength,
+            required: 4.,
+            actual: 3.,
+        })
+    );
+}
+
+#[test]
+fn weapon_types_choose_default_targeting() {
+    // pipe iron is blunt and leaves its targeting to its type
+    assert!(matches!(wielder(10.).attack_targeting_scheme(), TargetingScheme::SingleTarget));
+    assert!(matches!(WeaponType::Blade.targeting_scheme(), TargetingScheme::MultiTarget(2)));
+    assert!(matches!(WeaponType::Focus.targeting_scheme(), TargetingScheme::All));
+
+    let mut unarmed = wielder(10.);
+    unarmed.weapon = None;
+    assert!(matches!(unarmed.attack_targeting_scheme(), TargetingScheme::SingleTarget));
+}
+
+fn rolled(affix: &str, value: f64) -> RolledAffix {
+    RolledAffix { affix: affix.parse().unwrap(), value }
+}
+
+fn longsleeve(rarity: Rarity, affixes: Vec<RolledAffix>) -> ItemInstance<BodywearIdentifier> {
+    ItemInstance { base: "breakers_longsleeve".parse().unwrap(), rarity, affixes }
+}
+
+#[test]
+fn rolled_items_are_valid() {
+    let mut rng = Pcg32::seed_from_u64(7);
+    for rarity in [Rarity::Common, Rarity::Uncommon, Rarity::Rare, Rarity::Legendary] {
+        let item = ItemInstance::roll(Item::Bodywear("breakers_longsleeve".parse().unwrap()), rarity, &mut rng);
+        let party = Party { members: vec![wielder(10.)], inventory: vec![item] };
+        assert_eq!(party.validate(), Ok(()), "{:?}", rarity);
+    }
+}
+
+#[test]
+fn affixes_must_be_rollable() {
+    let bodywear = Item::Bodywear("breakers_longsleeve".parse().unwrap());
+    let validate = |item: ItemInstance<BodywearIdentifier>| {
+        let mut member = wielder(10.);
+        member.bodywear = Some(item);
+        party(member).validate()
+    };
+
+    assert_eq!(validate(longsleeve(Rarity::Uncommon, vec![rolled("strong", 2.)])), Ok(()));
+    assert_eq!(
+        validate(longsleeve(Rarity::Uncommon, vec![])),
+        Err(PartyError::AffixCountMismatch { item: bodywear, rarity: Rarity::Uncommon, expected: 1, actual: 0 })
+    );
+    assert_eq!(
+        validate(longsleeve(Rarity::Common, vec![rolled("strong", 2.)])),
+        Err(PartyError::AffixCountMismatch { item: bodywear, rarity: Rarity::Common, expected: 0, actual: 1 })
+    );
+    // quick never rolls on bodywear, and fireproof needs at least a rare
+    assert_eq!(
+        validate(longsleeve(Rarity::Uncommon, vec![rolled("quick", 2.)])),
+        Err(PartyError::AffixNotAllowed { item: bodywear, rarity: Rarity::Uncommon, affix: "quick".parse().unwrap() })
+    );
+    assert_eq!(
+        validate(longsleeve(Rarity::Uncommon, vec![rolled("fireproof", 0.1)])),
+        Err(PartyError::AffixNotAllowed {
+            item: bodywear,
+            rarity: Rarity::Uncommon,
+            affix: "fireproof".parse().unwrap()
+        })
+    );
+    assert_eq!(
+        validate(longsleeve(Rarity::Rare, vec![rolled("strong", 2.), rolled("strong", 3.)])),
+        Err(PartyError::DuplicateAffix { item: bodywear, affix: "strong".parse().unwrap() })
+    );
+    assert_eq!(
+        validate(longsleeve(Rarity::Uncommon, vec![rolled("strong", 10.)])),
+        Err(PartyError::AffixValueOutOfRange {
+            item: bodywear,
+            affix: "strong".parse().unwrap(),
+            value: 10.,
+            min: 1.,
+            max: 3.,
+        })
+    );
+}
+
+#[test]
+fn inventory_affixes_are_checked() {
+    let grenade = Item::Consumable("grenade".parse().unwrap());
+    let inventory = vec![ItemInstance { base: grenade, rarity: Rarity::Rare, affixes: vec![] }];
+    let party = Party { members: vec![wielder(10.)], inventory };
+    assert_eq!(party.validate(), Ok(()));
+
+    let inventory = vec![ItemInstance { base: grenade, rarity: Rarity::Common, affixes: vec![rolled("strong", 2.)] }];
+    let party = Party { members: vec![wielder(10.)], inventory };
+    assert_eq!(
+        party.validate(),
+        Err(PartyError::AffixCountMismatch { item: grenade, rarity: Rarity::Common, expected: 0, actual: 1 })
+    );
+}
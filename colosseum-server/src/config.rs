@@ -3,18 +3,74 @@
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
 use std::net::SocketAddrV4;
+use std::path::PathBuf;
 
+use colosseum::controller::ControllerKind;
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Clone, Copy, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Config {
     pub address: SocketAddr,
+    /// Seconds a readied client waits for an opponent before a bot takes the empty seat, never if absent
+    #[serde(default)]
+    pub bot_fill_delay: Option<u64>,
+    #[serde(default)]
+    pub bot_difficulty: Difficulty,
+    /// Where a replay of every finished match is written, none are kept if absent
+    #[serde(default)]
+    pub replay_directory: Option<PathBuf>,
+    /// Content directories laid over the embedded content in order, each adding to or replacing what came before
+    #[serde(default)]
+    pub content: Vec<PathBuf>,
+    /// Reloads the content directories when their files change, held back until no match is in progress
+    #[serde(default)]
+    pub watch_content: bool,
+    /// What happens to clients whose content differs from the server's
+    #[serde(default)]
+    pub content_mismatch: ContentMismatch,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub enum ContentMismatch {
+    /// Turn the client away with a rejection, and refuse its parties
+    Reject,
+    /// Log the differences and play on regardless
+    Warn,
+    /// Send the client the server's content to play with
+    #[default]
+    Push,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn controller(&self) -> ControllerKind {
+        match self {
+            Difficulty::Easy => ControllerKind::Random,
+            Difficulty::Normal => ControllerKind::Greedy,
+            Difficulty::Hard => ControllerKind::Mcts,
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self { address: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 20000)) }
+        Self {
+            address: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 20000)),
+            bot_fill_delay: Some(30),
+            bot_difficulty: Difficulty::default(),
+            replay_directory: Some(PathBuf::from("replays")),
+            content: vec![],
+            watch_content: false,
+            content_mismatch: ContentMismatch::default(),
+        }
     }
 }
 
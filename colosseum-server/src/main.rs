@@ -4,30 +4,47 @@ mod config;
 
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::convert::TryFrom;
-use std::convert::TryInto;
 use std::fs;
 use std::io::Write;
 use std::net::SocketAddr;
 use std::path::Path;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::thread;
 use std::thread::sleep;
-use std::thread::{self,};
 use std::time::Duration;
 use std::time::Instant;
 
 use colosseum::combat_event::CombatEvent;
 use colosseum::combat_state::CombatState;
-use colosseum::message::Message;
-use colosseum::message::MessageType;
-use colosseum::message::ProtocolVersion;
+use colosseum::content;
+use colosseum::content::ContentWatcher;
+use colosseum::content::Registry;
+use colosseum::controller::is_valid_event;
+use colosseum::controller::ControllerKind;
+use colosseum::encounter::Encounter;
+use colosseum::encounter::EncounterIdentifier;
+use colosseum::message::ContentPush;
+use colosseum::message::Encounters;
+use colosseum::message::Envelope;
+use colosseum::message::Handshake;
+use colosseum::message::Loot;
+use colosseum::message::Payload;
+use colosseum::message::Rejection;
+use colosseum::message::RelayedEvent;
+use colosseum::message::Rosters;
 use colosseum::message::TakeTurn;
+use colosseum::message::PROTOCOL_VERSION;
 use colosseum::party::Party;
+use colosseum::replay::Replay;
+use colosseum::roster::Roster;
 use colosseum::target::Target;
 use config::Config;
+use config::ContentMismatch;
+use crossbeam::channel::Receiver;
 use crossbeam::channel::Sender;
 use crossbeam::channel::TryRecvError;
 use laminar::Config as NetworkConfig;
@@ -35,30 +52,61 @@ use laminar::Packet;
 use laminar::SocketEvent;
 use log::error;
 use log::info;
+use log::warn;
 
 pub trait Client {
-    fn send_message<T: TryInto<Message, Error = bincode::Error>>(
-        &self,
-        sender: &Sender<Packet>,
-        payload: T,
-    ) -> anyhow::Result<()>;
+    /// Sends the payload in an envelope, logging rather than returning failures to send
+    fn send_message(&self, sender: &Sender<Packet>, payload: Payload);
 }
 
 impl Client for SocketAddr {
-    fn send_message<T: TryInto<Message, Error = bincode::Error>>(
-        &self,
-        sender: &Sender<Packet>,
-        payload: T,
-    ) -> anyhow::Result<()> {
-        let message: Message = payload.try_into()?;
-        let payload = bincode::serialize(&message)?;
-        sender.send(Packet::reliable_ordered(*self, payload, None))?;
-        Ok(())
+    fn send_message(&self, sender: &Sender<Packet>, payload: Payload) {
+        let name = payload.to_string();
+        if let Err(e) = send_envelope(sender, *self, &Envelope::new(payload)) {
+            error!("Failed to send {} to {}: {}", name, self, e);
+        }
+    }
+}
+
+fn send_envelope(sender: &Sender<Packet>, address: SocketAddr, envelope: &Envelope) -> anyhow::Result<()> {
+    let bytes = envelope.encode()?;
+    sender.send(Packet::reliable_ordered(address, bytes, None))?;
+    Ok(())
+}
+
+/// Who takes turns for a participant
+enum Seat {
+    Remote(SocketAddr),
+    Bot(BotWorker),
+}
+
+/// A bot deciding its turns on a thread of its own, so a long search never holds up the server loop
+struct BotWorker {
+    requests: Sender<(CombatState, Target)>,
+    events: Receiver<CombatEvent>,
+}
+
+impl BotWorker {
+    /// Builds the controller on the worker thread, which stops once the worker is dropped
+    fn spawn(kind: ControllerKind, seed: u64) -> Self {
+        let (requests, pending) = crossbeam::channel::unbounded::<(CombatState, Target)>();
+        let (decided, events) = crossbeam::channel::unbounded();
+
+        thread::spawn(move || {
+            let mut controller = kind.build(seed);
+            for (state, actor) in pending {
+                if decided.send(controller.choose(&state, actor)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { requests, events }
     }
 }
 
 struct Participant {
-    pub address: SocketAddr,
+    pub seat: Seat,
     pub ownership: Vec<Target>,
 }
 
@@ -66,6 +114,13 @@ struct Match {
     pub participants: Vec<Participant>,
     pub spectators: Vec<SocketAddr>,
     pub combat_state: CombatState,
+    /// The combatant a remote client or bot has been asked to take a turn for
+    pub turn: Option<Target>,
+    /// The encounter fought, players are always the first party of an encounter
+    pub encounter: Option<EncounterIdentifier>,
+    pub started: Instant,
+    pub replay: Replay,
+    pub replay_directory: Option<PathBuf>,
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -78,6 +133,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = load_config();
     let address = config.address;
 
+    let registry = content::install(Registry::load(&config.content)?);
+    info!("Loaded content version {}", registry.version());
+
     let mut socket = laminar::Socket::bind_with_config(
         address,
         NetworkConfig {
@@ -100,146 +158,212 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }));
 
     let mut clients: Vec<SocketAddr> = vec![];
-    let mut ready_clients: Vec<(SocketAddr, Party)> = vec![];
+    let mut rejected_clients: HashMap<SocketAddr, String> = HashMap::default();
+    let mut sequences: HashMap<SocketAddr, u64> = HashMap::default();
+    let mut ready_clients: Vec<(SocketAddr, Party, Instant)> = vec![];
     let mut matches_by_client: HashMap<SocketAddr, Rc<RefCell<Match>>> = HashMap::default();
+    let mut content_watcher = match config.watch_content {
+        true => Some(ContentWatcher::new(config.content.clone(), ContentWatcher::DEFAULT_INTERVAL)),
+        false => None,
+    };
 
     loop {
+        for match_ in matches_by_client.values() {
+            poll_bot(&mut match_.borrow_mut(), &sender);
+        }
+
+        if let Some(watcher) = &mut content_watcher {
+            if matches_by_client.values().all(|match_| match_.borrow().combat_state.is_over()) {
+                match watcher.poll() {
+                    Some(Ok(registry)) => info!("Reloaded content version {}", registry.version()),
+                    Some(Err(e)) => error!("Kept the previous content, reloading failed: {}", e),
+                    None => (),
+                }
+            }
+        }
+
+        if let Some(delay) = config.bot_fill_delay {
+            if let [(_, _, readied_at)] = ready_clients[..] {
+                if readied_at.elapsed() >= Duration::from_secs(delay) {
+                    let (address, party, _) = ready_clients.pop().unwrap();
+                    info!("Seating a {:?} bot against {}", config.bot_difficulty, address);
+
+                    let bot_party = party.clone();
+                    let bot = Seat::Bot(BotWorker::spawn(config.bot_difficulty.controller(), rand::random()));
+                    let combat_state = CombatState::new(vec![party, bot_party], rand::random());
+                    let seats = vec![Seat::Remote(address), bot];
+                    let match_ = start_match(seats, combat_state, None, &sender, config.replay_directory.clone());
+                    matches_by_client.insert(address, match_);
+                }
+            }
+        }
+
         match &receiver {
             Some(recv) => match recv.try_recv() {
                 Ok(message) => match message {
                     SocketEvent::Packet(packet) => {
-                        let message = bincode::deserialize::<Message>(packet.payload()).unwrap();
-                        info!("Received message from {}: {:?}", packet.addr(), message.type_);
+                        let envelope = match Envelope::decode(packet.payload()) {
+                            Ok(envelope) => envelope,
+                            Err(e) => {
+                                warn!("Ignored a message from {}: {}", packet.addr(), e);
+                                continue;
+                            },
+                        };
+                        info!("Received message {} from {}: {}", envelope.sequence, packet.addr(), envelope.payload);
+
+                        // a client's handshake starts its numbering over, as it may have restarted
+                        let stale = !matches!(envelope.payload, Payload::Handshake(_))
+                            && sequences.get(&packet.addr()).is_some_and(|last| *last >= envelope.sequence);
+                        if stale {
+                            warn!("Ignored stale message {} from {}", envelope.sequence, packet.addr());
+                            continue;
+                        }
+                        sequences.insert(packet.addr(), envelope.sequence);
+
                         match matches_by_client.get(&packet.addr()) {
                             Some(match_) => {
                                 let mut match_ = match_.borrow_mut();
-                                match message.type_ {
-                                    MessageType::CombatEvent => {
-                                        let event = CombatEvent::try_from(&message).unwrap();
-
-                                        // propogate message to participants
-                                        for participant in &match_.participants {
-                                            participant.address.send_message(&sender, &event).unwrap();
-                                        }
-
-                                        // propogate message to spectators
-                                        for spectator in &match_.spectators {
-                                            spectator.send_message(&sender, &event).unwrap();
+                                match envelope.payload {
+                                    Payload::CombatEvent(event) => {
+                                        if !holds_turn(&match_, packet.addr(), &event) {
+                                            warn!("Ignored an event from {} out of turn", packet.addr());
+                                            continue;
                                         }
 
-                                        match_.combat_state.process_event(&event);
-
-                                        // tell next client to take a turn
-                                        let ready = match_.combat_state.next_combatant();
-                                        let mut owner = None;
-                                        for i in 0..match_.participants.len() {
-                                            if match_.participants[i].ownership.contains(&ready) {
-                                                owner = Some(i);
-                                                break;
-                                            }
+                                        let valid = match_
+                                            .turn
+                                            .is_some_and(|turn| is_valid_event(&match_.combat_state, turn, &event));
+                                        if !valid {
+                                            warn!("Rejected an invalid event from {}: {:?}", packet.addr(), event);
+                                            resync(&match_, &sender, packet.addr());
+                                            continue;
                                         }
 
-                                        match owner {
-                                            Some(owner) => {
-                                                info!(
-                                                    "Requested that {} takes a turn for {}",
-                                                    match_.participants[owner].address,
-                                                    match_.combat_state.parties[ready.party_index].members
-                                                        [ready.member_index]
-                                                        .name
-                                                );
-                                                match_.participants[owner]
-                                                    .address
-                                                    .send_message(&sender, &TakeTurn { target: ready })
-                                                    .unwrap();
-                                            },
-                                            None => error!("Match participant has no owner but needs to take a turn"),
-                                        }
+                                        broadcast_event(&mut match_, &sender, &event);
+                                        request_turn(&mut match_, &sender);
                                     },
+                                    Payload::Resync => resync(&match_, &sender, packet.addr()),
                                     _ => (),
                                 }
                             },
                             None => {
                                 if clients.contains(&packet.addr()) {
-                                    if message.type_ == MessageType::Party {
-                                        let party = Party::try_from(&message).unwrap();
-                                        ready_clients.push((packet.addr().clone(), party));
-
-                                        if ready_clients.len() > 1 {
-                                            let (addr2, party2) = ready_clients.pop().unwrap();
-                                            let (addr1, party1) = ready_clients.pop().unwrap();
-
-                                            let combat_state = CombatState { parties: vec![party1, party2] };
-
-                                            addr1.send_message(&sender, &combat_state).unwrap();
-                                            addr2.send_message(&sender, &combat_state).unwrap();
-
-                                            let target_list = combat_state.get_target_list();
-                                            let ownership1 = target_list
-                                                .iter()
-                                                .filter(|target| target.party_index == 0)
-                                                .copied()
-                                                .collect();
-                                            let ownership2 = target_list
-                                                .iter()
-                                                .filter(|target| target.party_index == 1)
-                                                .copied()
-                                                .collect();
-
-                                            let match_ = Rc::new(RefCell::new(Match {
-                                                participants: vec![
-                                                    Participant { address: addr1, ownership: ownership1 },
-                                                    Participant { address: addr2, ownership: ownership2 },
-                                                ],
-                                                spectators: vec![],
-                                                combat_state,
-                                            }));
-
-                                            {
-                                                let mut match_ = match_.borrow_mut();
-
-                                                let ready = match_.combat_state.next_combatant();
-                                                let mut owner = None;
-                                                for i in 0..match_.participants.len() {
-                                                    if match_.participants[i].ownership.contains(&ready) {
-                                                        owner = Some(i);
-                                                        break;
-                                                    }
-                                                }
-
-                                                match owner {
-                                                    Some(owner) => {
-                                                        info!(
-                                                            "Requested that {} takes a turn for {}",
-                                                            match_.participants[owner].address,
-                                                            match_.combat_state.parties[ready.party_index].members
-                                                                [ready.member_index]
-                                                                .name
-                                                        );
-                                                        match_.participants[owner]
-                                                            .address
-                                                            .send_message(&sender, &TakeTurn { target: ready })
-                                                            .unwrap();
-                                                    },
-                                                    None => {
-                                                        error!("Match participant has no owner but needs to take a turn")
-                                                    },
-                                                }
-                                            }
-
-                                            matches_by_client.insert(addr1, match_.clone());
-                                            matches_by_client.insert(addr2, match_);
+                                    if let Payload::ChallengeEncounter(challenge) = envelope.payload {
+                                        if let Some(reason) = rejected_clients.get(&packet.addr()) {
+                                            error!("Rejected challenge from {}: {}", packet.addr(), reason);
+                                            continue;
+                                        }
+
+                                        if let Err(e) = challenge.party.validate() {
+                                            error!("Rejected party from {}: {}", packet.addr(), e);
+                                            continue;
                                         }
+
+                                        let encounter = <&Encounter>::from(challenge.encounter);
+                                        info!("{} challenged {}", packet.addr(), challenge.encounter);
+
+                                        let combat_state = encounter.combat_state(challenge.party, rand::random());
+                                        let bot = Seat::Bot(BotWorker::spawn(encounter.controller, rand::random()));
+                                        let seats = vec![Seat::Remote(packet.addr()), bot];
+                                        let match_ = start_match(
+                                            seats,
+                                            combat_state,
+                                            Some(challenge.encounter),
+                                            &sender,
+                                            config.replay_directory.clone(),
+                                        );
+                                        matches_by_client.insert(packet.addr(), match_);
+                                        continue;
                                     }
-                                } else if message.type_ == MessageType::ProtocolVersion {
-                                    let protover = Message::try_from(&ProtocolVersion(0)).unwrap();
-                                    sender
-                                        .send(Packet::reliable_ordered(
+
+                                    let party = match envelope.payload {
+                                        Payload::Party(party) => party,
+                                        Payload::ChooseRoster(choice) => <&Roster>::from(choice.roster).party(),
+                                        _ => continue,
+                                    };
+
+                                    if let Some(reason) = rejected_clients.get(&packet.addr()) {
+                                        error!("Rejected party from {}: {}", packet.addr(), reason);
+                                        continue;
+                                    }
+
+                                    if let Err(e) = party.validate() {
+                                        error!("Rejected party from {}: {}", packet.addr(), e);
+                                        continue;
+                                    }
+
+                                    ready_clients.push((packet.addr(), party, Instant::now()));
+
+                                    if ready_clients.len() > 1 {
+                                        let (addr2, party2, _) = ready_clients.pop().unwrap();
+                                        let (addr1, party1, _) = ready_clients.pop().unwrap();
+
+                                        let combat_state = CombatState::new(vec![party1, party2], rand::random());
+                                        let seats = vec![Seat::Remote(addr1), Seat::Remote(addr2)];
+                                        let replay_directory = config.replay_directory.clone();
+                                        let match_ = start_match(seats, combat_state, None, &sender, replay_directory);
+
+                                        matches_by_client.insert(addr1, match_.clone());
+                                        matches_by_client.insert(addr2, match_);
+                                    }
+                                } else if let Payload::Handshake(theirs) = envelope.payload {
+                                    let ours = Handshake::current();
+                                    if envelope.version != PROTOCOL_VERSION {
+                                        let reason = format!(
+                                            "client speaks protocol version {}, the server speaks {}",
+                                            envelope.version, PROTOCOL_VERSION
+                                        );
+                                        warn!("Rejected {}: {}", packet.addr(), reason);
+                                        rejected_clients.insert(packet.addr(), reason.clone());
+                                        let rejection = Payload::Rejected(Rejection { reason });
+                                        packet.addr().send_message(&sender, rejection);
+                                        // builds from before rejections existed only read the handshake, and turn away
+                                        // a server whose version differs
+                                        packet.addr().send_message(&sender, Payload::Handshake(ours));
+                                        continue;
+                                    }
+
+                                    rejected_clients.remove(&packet.addr());
+                                    let differences = ours.content.differences(&theirs.content);
+                                    if !differences.is_empty() {
+                                        warn!(
+                                            "Content of {} differs from version {} in {} entries",
                                             packet.addr(),
-                                            bincode::serialize(&protover).unwrap(),
-                                            None,
-                                        ))
-                                        .unwrap();
+                                            ours.content.version,
+                                            differences.len()
+                                        );
+                                        for difference in &differences {
+                                            info!("  {}", difference);
+                                        }
+
+                                        match config.content_mismatch {
+                                            ContentMismatch::Reject => {
+                                                let reason = format!(
+                                                    "content differs from the server's version {} in {} entries",
+                                                    ours.content.version,
+                                                    differences.len()
+                                                );
+                                                rejected_clients.insert(packet.addr(), reason.clone());
+                                                let rejection = Payload::Rejected(Rejection { reason });
+                                                packet.addr().send_message(&sender, rejection);
+                                                continue;
+                                            },
+                                            ContentMismatch::Warn => (),
+                                            ContentMismatch::Push => {
+                                                let push = ContentPush { files: content::registry().files().to_vec() };
+                                                packet.addr().send_message(&sender, Payload::ContentPush(push));
+                                            },
+                                        }
+                                    }
+
+                                    // sent last so pushed content is installed by the time the client hears back
+                                    packet.addr().send_message(&sender, Payload::Handshake(ours));
+
+                                    let rosters = content::registry().rosters.identifiers().collect();
+                                    packet.addr().send_message(&sender, Payload::Rosters(Rosters { rosters }));
+
+                                    let encounters = content::registry().encounters.identifiers().collect();
+                                    packet.addr().send_message(&sender, Payload::Encounters(Encounters { encounters }));
                                 }
                             },
                         }
@@ -266,6 +390,202 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Sends the initial state to every remote seat and hands out the first turn, seats are in the order of the parties
+fn start_match(
+    seats: Vec<Seat>,
+    combat_state: CombatState,
+    encounter: Option<EncounterIdentifier>,
+    sender: &Sender<Packet>,
+    replay_directory: Option<PathBuf>,
+) -> Rc<RefCell<Match>> {
+    let target_list = combat_state.get_target_list();
+
+    let participants = seats
+        .into_iter()
+        .enumerate()
+        .map(|(party_index, seat)| {
+            if let Seat::Remote(address) = seat {
+                address.send_message(sender, Payload::CombatState(combat_state.clone()));
+            }
+
+            let ownership = target_list.iter().filter(|target| target.party_index == party_index).copied().collect();
+            Participant { seat, ownership }
+        })
+        .collect();
+
+    let replay = Replay::new(combat_state.clone());
+    let mut match_ = Match {
+        participants,
+        spectators: vec![],
+        combat_state,
+        turn: None,
+        encounter,
+        started: Instant::now(),
+        replay,
+        replay_directory,
+    };
+    request_turn(&mut match_, sender);
+
+    Rc::new(RefCell::new(match_))
+}
+
+/// Applies an event to the server's copy and relays it, with the resulting checksum, to everyone watching
+fn broadcast_event(match_: &mut Match, sender: &Sender<Packet>, event: &CombatEvent) {
+    match_.combat_state.process_event(event);
+    match_.replay.record(match_.started.elapsed(), event.clone());
+
+    let relayed = RelayedEvent { event: event.clone(), checksum: match_.combat_state.checksum() };
+
+    // propogate message to participants
+    for participant in &match_.participants {
+        if let Seat::Remote(address) = participant.seat {
+            address.send_message(sender, Payload::RelayedEvent(relayed.clone()));
+        }
+    }
+
+    // propogate message to spectators
+    for spectator in &match_.spectators {
+        spectator.send_message(sender, Payload::RelayedEvent(relayed.clone()));
+    }
+}
+
+/// Whether the event comes from the client that was asked to take the current turn, for the combatant taking it
+fn holds_turn(match_: &Match, address: SocketAddr, event: &CombatEvent) -> bool {
+    let turn = match match_.turn {
+        Some(turn) => turn,
+        None => return false,
+    };
+
+    event.source().unwrap_or(turn) == turn
+        && match_.participants.iter().any(|participant| {
+            matches!(participant.seat, Seat::Remote(seat) if seat == address) && participant.ownership.contains(&turn)
+        })
+}
+
+/// Sends the server's state to a client that has desynced, along with its turn again if it was taking one
+fn resync(match_: &Match, sender: &Sender<Packet>, address: SocketAddr) {
+    info!("Resyncing {}", address);
+    address.send_message(sender, Payload::CombatState(match_.combat_state.clone()));
+
+    if let Some(target) = match_.turn {
+        let owns_turn = match_.participants.iter().any(|participant| {
+            matches!(participant.seat, Seat::Remote(seat) if seat == address) && participant.ownership.contains(&target)
+        });
+
+        if owns_turn {
+            let checksum = match_.combat_state.checksum();
+            address.send_message(sender, Payload::TakeTurn(TakeTurn { target, checksum }));
+        }
+    }
+}
+
+/// Hands out the next turn, to a remote client or to a bot that plays it once [`poll_bot`] finds it decided
+fn request_turn(match_: &mut Match, sender: &Sender<Packet>) {
+    match_.turn = None;
+
+    if match_.combat_state.is_over() {
+        for participant in &match_.participants {
+            if let Seat::Remote(address) = participant.seat {
+                address.send_message(sender, Payload::Victory);
+            }
+        }
+
+        for spectator in &match_.spectators {
+            spectator.send_message(sender, Payload::Victory);
+        }
+
+        match_.replay.finish(match_.combat_state.clone());
+        if let Some(directory) = &match_.replay_directory {
+            if let Err(e) = write_replay(directory, &match_.replay) {
+                error!("Failed to write replay: {}", e);
+            }
+        }
+
+        if let Some(encounter) = match_.encounter {
+            award_loot(match_, sender, encounter);
+        }
+
+        return;
+    }
+
+    let ready = match match_.combat_state.next_combatant() {
+        Some(ready) => ready,
+        None => {
+            error!("Match has no combatant able to take a turn");
+            return;
+        },
+    };
+    let name = match_.combat_state.parties[ready.party_index].members[ready.member_index].name.clone();
+    let owner = match_.participants.iter().position(|participant| participant.ownership.contains(&ready));
+
+    match owner {
+        Some(owner) => match &mut match_.participants[owner].seat {
+            Seat::Remote(address) => {
+                info!("Requested that {} takes a turn for {}", address, name);
+                let checksum = match_.combat_state.checksum();
+                address.send_message(sender, Payload::TakeTurn(TakeTurn { target: ready, checksum }));
+                match_.turn = Some(ready);
+            },
+            Seat::Bot(worker) => match worker.requests.send((match_.combat_state.clone(), ready)) {
+                Ok(()) => match_.turn = Some(ready),
+                Err(_) => error!("Bot stopped taking turns before {} could act", name),
+            },
+        },
+        None => error!("Match participant has no owner but needs to take a turn"),
+    }
+}
+
+/// Plays the turn a bot was asked to take if it has decided on it, then hands out the next turn
+fn poll_bot(match_: &mut Match, sender: &Sender<Packet>) {
+    let turn = match match_.turn {
+        Some(turn) => turn,
+        None => return,
+    };
+    let worker = match match_.participants.iter().find(|participant| participant.ownership.contains(&turn)) {
+        Some(Participant { seat: Seat::Bot(worker), .. }) => worker,
+        _ => return,
+    };
+
+    let event = match worker.events.try_recv() {
+        Ok(event) => event,
+        Err(TryRecvError::Empty) => return,
+        Err(TryRecvError::Disconnected) => {
+            error!("Bot stopped taking turns mid match");
+            match_.turn = None;
+            return;
+        },
+    };
+
+    let name = &match_.combat_state.parties[turn.party_index].members[turn.member_index].name;
+    info!("Bot took a turn for {}", name);
+    broadcast_event(match_, sender, &event);
+    request_turn(match_, sender);
+}
+
+/// Rolls the encounter's loot into the players' inventory if they won, and lets them know what they won
+///
+/// Called once the replay is finished, so replays end on the state combat ended in and still verify
+fn award_loot(match_: &mut Match, sender: &Sender<Packet>, encounter: EncounterIdentifier) {
+    let items = <&Encounter>::from(encounter).award_loot(&mut match_.combat_state, &mut rand::thread_rng());
+    if items.is_empty() {
+        return;
+    }
+    info!("Awarded {} items for {}", items.len(), encounter);
+    if let Seat::Remote(address) = match_.participants[0].seat {
+        address.send_message(sender, Payload::Loot(Loot { items }));
+    }
+}
+
+fn write_replay(directory: &Path, replay: &Replay) -> anyhow::Result<()> {
+    fs::create_dir_all(directory)?;
+
+    let path = directory.join(format!("{}-{:08x}.replay", replay.header.started_at, rand::random::<u32>()));
+    replay.write(std::io::BufWriter::new(fs::File::create(&path)?))?;
+    info!("Wrote replay to {}", path.display());
+
+    Ok(())
+}
+
 fn load_config() -> Config {
     let path = Path::new("config.json");
 
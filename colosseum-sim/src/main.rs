@@ -0,0 +1,170 @@
+// Copyright 2021 Chay Nabors.
+
+mod report;
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Context;
+use clap::Parser;
+use colosseum_core::combat_event::CombatEvent;
+use colosseum_core::combat_state::CombatState;
+use colosseum_core::consumable::Consumable;
+use colosseum_core::content;
+use colosseum_core::content::ContentWatcher;
+use colosseum_core::content::Registry;
+use colosseum_core::controller::Controller;
+use colosseum_core::controller::ControllerKind;
+use colosseum_core::party::Party;
+use colosseum_core::skill::Skill;
+use colosseum_core::weapon::Weapon;
+use report::Report;
+
+/// Plays out battles between parties without a server and reports how they went
+#[derive(Parser)]
+struct Args {
+    /// Party JSON files, one per side of the battle
+    #[arg(required = true, num_args = 2..)]
+    parties: Vec<PathBuf>,
+
+    /// The number of battles to run
+    #[arg(short, long, default_value_t = 100)]
+    battles: u32,
+
+    /// Seed for the first battle, later battles use consecutive seeds
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Controller for each party in order, parties past the end use the last controller given
+    #[arg(short, long = "controller", default_value = "greedy")]
+    controllers: Vec<ControllerKind>,
+
+    /// Turns after which a battle is counted as a draw
+    #[arg(long, default_value_t = 1000)]
+    max_turns: u32,
+
+    /// Content directories laid over the embedded content, later directories take precedence
+    #[arg(long)]
+    content: Vec<PathBuf>,
+
+    /// Reloads the content directories between battles whenever their files change
+    #[arg(long)]
+    watch: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    content::install(Registry::load(&args.content)?);
+
+    let mut parties = vec![];
+    let mut party_names = vec![];
+    for path in &args.parties {
+        let party: Party = serde_json::from_slice(&fs::read(path).with_context(|| format!("reading {:?}", path))?)
+            .with_context(|| format!("parsing {:?}", path))?;
+        if let Err(e) = party.validate() {
+            bail!("{:?} is not a valid party: {}", path, e);
+        }
+
+        parties.push(party);
+        party_names.push(path.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+    }
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut report = Report::new(party_names, &parties);
+    let mut content_watcher = match args.watch {
+        true => Some(ContentWatcher::new(args.content.clone(), ContentWatcher::DEFAULT_INTERVAL)),
+        false => None,
+    };
+
+    for battle in 0..args.battles {
+        if let Some(watcher) = &mut content_watcher {
+            match watcher.poll() {
+                Some(Ok(registry)) => {
+                    eprintln!("Reloaded content version {} before battle {}", registry.version(), battle)
+                },
+                Some(Err(e)) => eprintln!("Kept the previous content, reloading failed: {}", e),
+                None => (),
+            }
+        }
+
+        let battle_seed = seed.wrapping_add(battle as u64);
+        let mut controllers: Vec<Box<dyn Controller>> = (0..parties.len())
+            .map(|party_index| {
+                let kind = args.controllers.get(party_index).or_else(|| args.controllers.last()).unwrap();
+                kind.build(battle_seed.wrapping_add(party_index as u64))
+            })
+            .collect();
+
+        run_battle(&parties, &mut controllers, battle_seed, args.max_turns, &mut report);
+    }
+
+    println!("Seed: {}", seed);
+    print!("{}", report);
+
+    Ok(())
+}
+
+fn run_battle(
+    parties: &[Party],
+    controllers: &mut [Box<dyn Controller>],
+    seed: u64,
+    max_turns: u32,
+    report: &mut Report,
+) {
+    let mut state = CombatState::new(parties.to_vec(), seed);
+    let mut turns = 0;
+
+    while !state.is_over() && turns < max_turns {
+        let actor = match state.next_combatant() {
+            Some(actor) => actor,
+            None => break,
+        };
+        let event = controllers[actor.party_index].choose(&state, actor);
+
+        let hp = total_hp(&state);
+        state.apply_event(&event);
+        if let Some(source) = damage_source(&state, &event) {
+            report.record_damage(source, hp - total_hp(&state));
+        }
+
+        let hp = total_hp(&state);
+        state.tick();
+        report.record_damage("Damage over time".into(), hp - total_hp(&state));
+
+        turns += 1;
+    }
+
+    report.battles += 1;
+    report.turns += turns as u64;
+    match state.victor() {
+        Some(victor) if state.is_over() => report.wins[victor] += 1,
+        _ => report.draws += 1,
+    }
+
+    for (party_index, party) in state.parties.iter().enumerate() {
+        for (member_index, member) in party.members.iter().enumerate() {
+            if member.alive() {
+                report.survivals[party_index][member_index] += 1;
+            }
+        }
+    }
+}
+
+fn total_hp(state: &CombatState) -> f64 {
+    state.parties.iter().flat_map(|party| &party.members).map(|member| member.hp).sum()
+}
+
+fn damage_source(state: &CombatState, event: &CombatEvent) -> Option<String> {
+    match event {
+        CombatEvent::AttackEvent { source, .. } => {
+            match state.parties[source.party_index].members[source.member_index].weapon_identifier() {
+                Some(weapon) => Some(format!("Attack ({})", <&Weapon>::from(weapon).display_name)),
+                None => Some("Attack (unarmed)".into()),
+            }
+        },
+        CombatEvent::ConsumableEvent { consumable, .. } => Some(<&Consumable>::from(*consumable).display_name.clone()),
+        CombatEvent::SkillEvent { skill, .. } => Some(<&Skill>::from(*skill).display_name.clone()),
+        CombatEvent::SkipEvent => None,
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2021 Chay Nabors.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use colosseum_core::party::Party;
+
+/// Statistics accumulated over a series of battles between the same parties
+pub struct Report {
+    pub party_names: Vec<String>,
+    pub member_names: Vec<Vec<String>>,
+    pub battles: u32,
+    pub wins: Vec<u32>,
+    pub draws: u32,
+    pub turns: u64,
+    pub damage_by_source: BTreeMap<String, f64>,
+    pub survivals: Vec<Vec<u32>>,
+}
+
+impl Report {
+    pub fn new(party_names: Vec<String>, parties: &[Party]) -> Self {
+        Self {
+            wins: vec![0; parties.len()],
+            member_names: parties
+                .iter()
+                .map(|party| party.members.iter().map(|member| member.name.clone()).collect())
+                .collect(),
+            survivals: parties.iter().map(|party| vec![0; party.members.len()]).collect(),
+            party_names,
+            battles: 0,
+            draws: 0,
+            turns: 0,
+            damage_by_source: BTreeMap::new(),
+        }
+    }
+
+    pub fn record_damage(&mut self, source: String, damage: f64) {
+        if damage > 0. {
+            *self.damage_by_source.entry(source).or_insert(0.) += damage;
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let battles = self.battles.max(1) as f64;
+
+        writeln!(f, "Battles: {}", self.battles)?;
+        writeln!(f, "Average turns: {:.2}", self.turns as f64 / battles)?;
+
+        writeln!(f)?;
+        writeln!(f, "Win rates:")?;
+        for (name, wins) in self.party_names.iter().zip(&self.wins) {
+            writeln!(f, "    {}: {:.1}%", name, *wins as f64 / battles * 100.)?;
+        }
+        writeln!(f, "    draws: {:.1}%", self.draws as f64 / battles * 100.)?;
+
+        writeln!(f)?;
+        writeln!(f, "Damage by source (total, per battle):")?;
+        for (source, damage) in &self.damage_by_source {
+            writeln!(f, "    {}: {:.1}, {:.2}", source, damage, damage / battles)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Survival by combatant:")?;
+        for (party_index, name) in self.party_names.iter().enumerate() {
+            for (member_name, survivals) in self.member_names[party_index].iter().zip(&self.survivals[party_index]) {
+                writeln!(f, "    {} / {}: {:.1}%", name, member_name, *survivals as f64 / battles * 100.)?;
+            }
+        }
+
+        Ok(())
+    }
+}